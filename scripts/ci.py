@@ -106,7 +106,7 @@ def check_docs() -> None:
 
 
 def lint() -> None:
-    """Lint with cargo clippy."""
+    """Lint with cargo clippy, with default and all features."""
     print_header("Linting with cargo clippy...")
     run_command(
         [
@@ -121,6 +121,21 @@ def lint() -> None:
         ]
     )
 
+    print_header("Linting with cargo clippy (all features)...")
+    run_command(
+        [
+            "cargo",
+            "+stable",
+            "clippy",
+            "--no-deps",
+            "--all-targets",
+            "--all-features",
+            "--",
+            "-D",
+            "warnings",
+        ]
+    )
+
 
 def build() -> None:
     """Run cargo build."""
@@ -131,10 +146,16 @@ def build() -> None:
 
 
 def run_tests_stable() -> None:
-    """Run tests with stable compiler."""
+    """Run tests with stable compiler, with default and all features."""
     print_header("Running tests (stable compiler)...")
     run_command(["cargo", "+stable", "test"], env={"RUSTFLAGS": "-D warnings"})
 
+    print_header("Running tests (stable compiler, all features)...")
+    run_command(
+        ["cargo", "+stable", "test", "--all-features"],
+        env={"RUSTFLAGS": "-D warnings"},
+    )
+
 
 def run_tests_beta() -> None:
     """Run tests with beta compiler."""
@@ -143,10 +164,16 @@ def run_tests_beta() -> None:
 
 
 def run_tests_msrv() -> None:
-    """Run tests with MSRV compiler."""
+    """Run tests with MSRV compiler, with default and all features."""
     print_header(f"Running tests (MSRV compiler ({MSRV}))...")
     run_command(["cargo", f"+{MSRV}", "test"], env={"RUSTFLAGS": "-D warnings"})
 
+    print_header(f"Running tests (MSRV compiler ({MSRV}), all features)...")
+    run_command(
+        ["cargo", f"+{MSRV}", "test", "--all-features"],
+        env={"RUSTFLAGS": "-D warnings"},
+    )
+
 
 def run_tests_leak_sanitizer() -> None:
     """Run tests with leak sanitizer."""
@@ -0,0 +1,157 @@
+use std::fmt::Display;
+
+/// The parts of a failed [`assert_eq!`] or [`assert_ne!`] assertion.
+///
+/// See [`PanicData::assertion`].
+///
+/// [`PanicData::assertion`]: crate::PanicData::assertion
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AssertionFailure {
+    /// The comparison that failed.
+    pub operator: AssertionOperator,
+
+    /// The [`Debug`] representation of the left value.
+    pub left: String,
+
+    /// The [`Debug`] representation of the right value.
+    pub right: String,
+
+    /// The custom message passed to the assertion, if any.
+    pub message: Option<String>,
+}
+
+/// The comparison of a failed assertion, for [`AssertionFailure`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AssertionOperator {
+    /// `left == right`, from [`assert_eq!`] (or [`debug_assert_eq!`]).
+    Eq,
+
+    /// `left != right`, from [`assert_ne!`] (or [`debug_assert_ne!`]).
+    Ne,
+
+    /// `left matches right`, from the unstable `assert_matches!` (or `debug_assert_matches!`).
+    Matches,
+}
+
+impl Display for AssertionOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Matches => "matches",
+        })
+    }
+}
+
+/// Parses a failed assertion out of a panic message in the standard library's format:
+///
+/// ```text
+/// assertion `left == right` failed: optional custom message
+///   left: 1
+///  right: 2
+/// ```
+pub(crate) fn parse_assertion_failure(message: &str) -> Option<AssertionFailure> {
+    // Values are printed last, and may span multiple lines (with a custom `Debug` impl), so search
+    // for their labels from the end
+    let (rest, right) = message.rsplit_once("\n right: ")?;
+    let (header, left) = rest.rsplit_once("\n  left: ")?;
+
+    let rest = header.strip_prefix("assertion `left ")?;
+    let (operator, rest) = rest.split_once(" right` failed")?;
+    let operator = match operator {
+        "==" => AssertionOperator::Eq,
+        "!=" => AssertionOperator::Ne,
+        "matches" => AssertionOperator::Matches,
+        _ => return None,
+    };
+    let message = match rest {
+        "" => None,
+        rest => Some(rest.strip_prefix(": ")?.to_string()),
+    };
+
+    Some(AssertionFailure {
+        operator,
+        left: left.to_string(),
+        right: right.to_string(),
+        message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::catch;
+
+    /// This test ensures [`assert_eq!`] failures are parsed, with and without a custom message.
+    #[test]
+    fn parses_assert_eq() {
+        let panic_data = catch(|| assert_eq!(std::hint::black_box(1), 2)).unwrap_err();
+        assert_eq!(
+            panic_data.assertion(),
+            Some(AssertionFailure {
+                operator: AssertionOperator::Eq,
+                left: String::from("1"),
+                right: String::from("2"),
+                message: None,
+            })
+        );
+
+        let panic_data = catch(|| {
+            assert_eq!(std::hint::black_box("a"), "b", "custom: {}\nline", 3);
+        })
+        .unwrap_err();
+        assert_eq!(
+            panic_data.assertion(),
+            Some(AssertionFailure {
+                operator: AssertionOperator::Eq,
+                left: String::from("\"a\""),
+                right: String::from("\"b\""),
+                message: Some(String::from("custom: 3\nline")),
+            })
+        );
+    }
+
+    /// This test ensures [`assert_ne!`] failures are parsed, including multi-line values.
+    #[test]
+    fn parses_assert_ne_multiline() {
+        struct MultiLine;
+
+        impl std::fmt::Debug for MultiLine {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "first\nsecond")
+            }
+        }
+
+        impl PartialEq for MultiLine {
+            fn eq(&self, _: &Self) -> bool {
+                true
+            }
+        }
+
+        let panic_data = catch(|| assert_ne!(MultiLine, MultiLine)).unwrap_err();
+        assert_eq!(
+            panic_data.assertion(),
+            Some(AssertionFailure {
+                operator: AssertionOperator::Ne,
+                left: String::from("first\nsecond"),
+                right: String::from("first\nsecond"),
+                message: None,
+            })
+        );
+    }
+
+    /// This test ensures other panics, including plain [`assert!`] failures, are not parsed.
+    #[test]
+    fn ignores_other_panics() {
+        let cases: [fn(); 3] = [
+            || assert!(std::hint::black_box(false)),
+            || panic!("assertion `left == right` failed"),
+            || std::panic::panic_any(42_u8),
+        ];
+
+        for f in cases {
+            assert_eq!(catch(f).unwrap_err().assertion(), None);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+/// A shared flag used to ask threads to stop cooperatively.
+///
+/// Threads cannot be stopped from the outside, so code that may be asked to stop (for example, a
+/// [`Supervisor`] worker or [`CatchGroup`] member) must check [`is_cancelled`] regularly and return
+/// once it is set.
+///
+/// Cloned tokens share the same flag. A [child token](CancellationToken::child_token) is cancelled
+/// whenever its parent is, but can also be cancelled on its own.
+///
+/// [`Supervisor`]: crate::Supervisor
+/// [`CatchGroup`]: crate::CatchGroup
+/// [`is_cancelled`]: CancellationToken::is_cancelled
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    parent: Option<CancellationToken>,
+}
+
+impl CancellationToken {
+    /// Creates a new, uncancelled token.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new, uncancelled token that is also cancelled whenever this token is.
+    #[must_use]
+    pub fn child_token(&self) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                cancelled: AtomicBool::new(false),
+                parent: Some(self.clone()),
+            }),
+        }
+    }
+
+    /// Cancels this token (and all of its children).
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Release);
+    }
+
+    /// Returns whether this token (or any of its ancestors) has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Acquire)
+            || self.inner.parent.as_ref().is_some_and(Self::is_cancelled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// This test ensures cancelling a token cancels its clones and children, but not its parent.
+    #[test]
+    fn cancellation_propagates_to_children() {
+        let parent = CancellationToken::new();
+        let child = parent.child_token();
+        let grandchild = child.child_token();
+
+        child.cancel();
+        assert!(!parent.is_cancelled());
+        assert!(child.clone().is_cancelled());
+        assert!(grandchild.is_cancelled());
+
+        let sibling = parent.child_token();
+        assert!(!sibling.is_cancelled());
+        parent.cancel();
+        assert!(sibling.is_cancelled());
+    }
+}
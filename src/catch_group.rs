@@ -0,0 +1,239 @@
+use std::{
+    io,
+    panic::UnwindSafe,
+    sync::{Arc, Mutex, PoisonError},
+    thread::JoinHandle,
+};
+
+use crate::{
+    CancellationToken, PanicData, catch_inner, thread_local_catch_stack::CaptureBacktrace,
+};
+
+/// A group of threads that fails fast: the first panic in any member cancels all of the others.
+///
+/// Each member runs on its own thread, within a [`chillpill::catch`], and is given a shared
+/// [`CancellationToken`]. As soon as any member panics, the token is cancelled, asking the rest of
+/// the members to stop. Threads cannot be stopped from the outside, so members must check the token
+/// regularly and return once it is cancelled.
+///
+/// # Example
+///
+/// ```
+/// use chillpill::CatchGroup;
+///
+/// let mut group = CatchGroup::new();
+/// group.spawn(|_| panic!("bad input")).unwrap();
+/// group
+///     .spawn(|token| {
+///         while !token.is_cancelled() {
+///             // Do some work
+///         }
+///     })
+///     .unwrap();
+///
+/// let panics = group.join().unwrap_err();
+/// assert_eq!(panics.first.payload_as_string().as_deref(), Some("bad input"));
+/// ```
+///
+/// [`chillpill::catch`]: crate::catch
+#[derive(Debug)]
+pub struct CatchGroup<T> {
+    token: CancellationToken,
+    panics: Arc<Mutex<Vec<PanicData>>>,
+    handles: Vec<JoinHandle<Option<T>>>,
+}
+
+/// The panics of a [`CatchGroup`]'s members, in the order they occurred.
+#[derive(Debug)]
+pub struct GroupPanics {
+    /// The first panic, which cancelled the rest of the group.
+    pub first: PanicData,
+
+    /// Any panics that occurred after the first one.
+    pub subsequent: Box<[PanicData]>,
+}
+
+impl<T: Send + 'static> CatchGroup<T> {
+    /// Creates an empty group.
+    pub fn new() -> Self {
+        Self {
+            token: CancellationToken::new(),
+            panics: Arc::new(Mutex::new(Vec::new())),
+            handles: Vec::new(),
+        }
+    }
+
+    /// Returns the token shared by every member of the group.
+    ///
+    /// Cancelling it asks every member to stop, just like a panic would.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// Spawns a new member of the group, which runs `f` on its own thread.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the thread could not be spawned.
+    pub fn spawn<F>(&mut self, f: F) -> io::Result<()>
+    where
+        F: FnOnce(&CancellationToken) -> T + Send + UnwindSafe + 'static,
+    {
+        let token = self.token.clone();
+        let panics = Arc::clone(&self.panics);
+
+        let handle = std::thread::Builder::new().spawn(move || {
+            // Fatal panics pass through `catch_inner`, but should still cancel the group on their
+            // way out of the thread
+            let result =
+                std::panic::catch_unwind(|| catch_inner(|| f(&token), CaptureBacktrace::Default));
+
+            match result {
+                Ok(Ok(value)) => Some(value),
+                Ok(Err(panic_data)) => {
+                    // Record the panic before cancelling, so the first panic is recorded first
+                    panics
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .push(panic_data);
+                    token.cancel();
+
+                    None
+                }
+                Err(payload) => {
+                    token.cancel();
+                    std::panic::resume_unwind(payload);
+                }
+            }
+        })?;
+        self.handles.push(handle);
+
+        Ok(())
+    }
+
+    /// Waits for every member of the group to finish.
+    ///
+    /// # Errors
+    ///
+    /// Returns every member's panic data, in the order the panics occurred, if any member panicked.
+    ///
+    /// # Panics
+    ///
+    /// See [`chillpill::catch`]. Also resumes unwinding a member's [`FatalPanic`] (once every
+    /// member has finished).
+    ///
+    /// [`chillpill::catch`]: crate::catch
+    /// [`FatalPanic`]: crate::FatalPanic
+    pub fn join(self) -> Result<Vec<T>, GroupPanics> {
+        // Members catch their own panics, except for fatal ones
+        let mut fatal_payload = None;
+        let values: Vec<Option<T>> = self
+            .handles
+            .into_iter()
+            .map(|handle| {
+                handle.join().unwrap_or_else(|payload| {
+                    fatal_payload.get_or_insert(payload);
+                    None
+                })
+            })
+            .collect();
+        if let Some(payload) = fatal_payload {
+            std::panic::resume_unwind(payload);
+        }
+
+        let mut panics =
+            std::mem::take(&mut *self.panics.lock().unwrap_or_else(PoisonError::into_inner))
+                .into_iter();
+
+        match panics.next() {
+            Some(first) => Err(GroupPanics {
+                first,
+                subsequent: panics.collect(),
+            }),
+            None => Ok(values.into_iter().flatten().collect()),
+        }
+    }
+}
+
+impl<T: Send + 'static> Default for CatchGroup<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{panic::AssertUnwindSafe, time::Duration};
+
+    use super::*;
+
+    /// Helper function that blocks until `token` is cancelled.
+    fn wait_for_cancellation(token: &CancellationToken) {
+        while !token.is_cancelled() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// This test ensures a group without panics returns every member's value, in spawn order.
+    #[test]
+    fn returns_values_in_spawn_order() {
+        let mut group = CatchGroup::new();
+        for i in 0..4 {
+            group
+                .spawn(move |_| {
+                    std::thread::sleep(Duration::from_millis(4 - i));
+                    i
+                })
+                .unwrap();
+        }
+
+        assert_eq!(group.join().unwrap(), [0, 1, 2, 3]);
+    }
+
+    /// This test ensures the first panic cancels the other members, and that later panics are
+    /// returned after it.
+    #[test]
+    fn first_panic_cancels_group() {
+        let mut group = CatchGroup::new();
+        group
+            .spawn(|token| {
+                wait_for_cancellation(token);
+            })
+            .unwrap();
+        group
+            .spawn(|token| {
+                wait_for_cancellation(token);
+                panic!("second panic");
+            })
+            .unwrap();
+        group
+            .spawn(|_| {
+                panic!("first panic");
+            })
+            .unwrap();
+
+        let panics = group.join().unwrap_err();
+        assert_eq!(
+            panics.first.payload_as_string().as_deref(),
+            Some("first panic")
+        );
+        assert_eq!(panics.subsequent.len(), 1);
+        assert_eq!(
+            panics.subsequent[0].payload_as_string().as_deref(),
+            Some("second panic")
+        );
+    }
+
+    /// This test ensures a fatal panic cancels the group, and is resumed by `join`.
+    #[test]
+    fn fatal_panic_resumed_by_join() {
+        let mut group = CatchGroup::new();
+        group.spawn(wait_for_cancellation).unwrap();
+        group
+            .spawn(|_| crate::panic_fatal!("fatal member"))
+            .unwrap();
+
+        let payload = std::panic::catch_unwind(AssertUnwindSafe(|| group.join())).unwrap_err();
+        assert!(payload.is::<crate::FatalPanic>());
+    }
+}
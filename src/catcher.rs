@@ -0,0 +1,268 @@
+use std::{
+    panic::{PanicHookInfo, UnwindSafe},
+    sync::Arc,
+};
+
+use crate::{
+    PanicReporter, Result, catch_in_frame,
+    thread_local_catch_stack::{CaptureBacktrace, CatchFilter, CatchStackFrame},
+};
+
+/// A configurable [`chillpill::catch`].
+///
+/// `Catcher::new().catch(f)` behaves exactly like `chillpill::catch(f)`. Additional configuration
+/// is available through builder methods.
+///
+/// # Example
+///
+/// ```
+/// use std::panic::AssertUnwindSafe;
+///
+/// use chillpill::Catcher;
+///
+/// // Only catch panics with a `String` payload
+/// let catcher = Catcher::new().filter(|info| info.payload().is::<String>());
+///
+/// let panic_data = catcher.catch(|| panic!("{}", "formatted")).unwrap_err();
+/// assert_eq!(panic_data.payload_as_string().as_deref(), Some("formatted"));
+///
+/// // This panic's payload is an `i32`, so it is not caught
+/// let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+///     catcher.catch(|| std::panic::panic_any(42))
+/// }));
+/// result.unwrap_err();
+/// ```
+///
+/// [`chillpill::catch`]: crate::catch
+#[derive(Clone)]
+pub struct Catcher {
+    capture_backtrace: CaptureBacktrace,
+    filter: Option<Arc<CatchFilter>>,
+    label: Option<Arc<str>>,
+    tags: Option<Arc<[(String, String)]>>,
+    reporter: Option<Arc<dyn PanicReporter>>,
+}
+
+impl std::fmt::Debug for Catcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Catcher")
+            .field("capture_backtrace", &self.capture_backtrace)
+            .field("filter", &self.filter.as_ref().map(|_| ".."))
+            .field("label", &self.label)
+            .field("tags", &self.tags)
+            .field("reporter", &self.reporter.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+impl Catcher {
+    /// Creates a catcher with the same behavior as [`chillpill::catch`].
+    ///
+    /// [`chillpill::catch`]: crate::catch
+    pub fn new() -> Self {
+        Self {
+            capture_backtrace: CaptureBacktrace::Default,
+            filter: None,
+            label: None,
+            tags: None,
+            reporter: None,
+        }
+    }
+
+    /// Always captures a backtrace, like [`chillpill::catch_force_backtrace`].
+    ///
+    /// [`chillpill::catch_force_backtrace`]: crate::catch_force_backtrace
+    #[must_use]
+    pub fn force_backtrace(mut self) -> Self {
+        self.capture_backtrace = CaptureBacktrace::Always;
+        self
+    }
+
+    /// Never captures a backtrace, like [`chillpill::catch_never_backtrace`].
+    ///
+    /// [`chillpill::catch_never_backtrace`]: crate::catch_never_backtrace
+    #[must_use]
+    pub fn never_backtrace(mut self) -> Self {
+        self.capture_backtrace = CaptureBacktrace::Never;
+        self
+    }
+
+    /// Only catches panics for which `filter` returns `true`, replacing any previous filter.
+    ///
+    /// The filter is given the panic's [`PanicHookInfo`], which includes its payload and location.
+    /// Panics the filter rejects are left untouched: they keep unwinding past this catch, as if it
+    /// were not there. An outer `chillpill::catch` still reports their original location and
+    /// backtrace, and if nothing outer catches them, they are handled like any other uncaught panic
+    /// (by default, printing the usual panic message to `stderr`).
+    ///
+    /// The filter runs inside the panic hook, so it must not panic - doing so aborts the process.
+    #[must_use]
+    pub fn filter(
+        mut self,
+        filter: impl Fn(&PanicHookInfo<'_>) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Sets a label identifying this catcher to panic subscribers (see [`chillpill::subscribe`]),
+    /// replacing any previous label.
+    ///
+    /// [`chillpill::subscribe`]: crate::subscribe
+    #[must_use]
+    pub fn label(mut self, label: impl Into<Arc<str>>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Adds a key-value tag to the reports of panics this catcher catches (see
+    /// [`Catcher::reporter`] and [`PanicReport::tags`]).
+    ///
+    /// [`PanicReport::tags`]: crate::PanicReport::tags
+    #[must_use]
+    pub fn tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let mut tags = self.tags.as_deref().map_or_else(Vec::new, <[_]>::to_vec);
+        tags.push((key.into(), value.into()));
+        self.tags = Some(tags.into());
+        self
+    }
+
+    /// Reports every panic this catcher catches to `reporter`, replacing any previous reporter.
+    ///
+    /// Panics rejected by the filter are not reported by this catcher. To report to several
+    /// destinations, combine reporters with [`FanOut`].
+    ///
+    /// [`FanOut`]: crate::FanOut
+    #[must_use]
+    pub fn reporter(mut self, reporter: impl PanicReporter + 'static) -> Self {
+        self.reporter = Some(Arc::new(reporter));
+        self
+    }
+
+    /// Invokes a closure, capturing the cause, location, and backtrace of an unwinding panic if one
+    /// occurs (and is accepted by the filter, if any).
+    ///
+    /// See [`chillpill::catch`] for details.
+    ///
+    /// # Errors
+    ///
+    /// See [`chillpill::catch`].
+    ///
+    /// # Panics
+    ///
+    /// See [`chillpill::catch`]. Also resumes unwinding any panic rejected by the filter.
+    ///
+    /// [`chillpill::catch`]: crate::catch
+    pub fn catch<F: FnOnce() -> R + UnwindSafe, R>(&self, f: F) -> Result<R> {
+        let mut frame = CatchStackFrame::new(self.capture_backtrace);
+        frame.filter.clone_from(&self.filter);
+        frame.label.clone_from(&self.label);
+        frame.tags.clone_from(&self.tags);
+        frame.reporter.clone_from(&self.reporter);
+
+        catch_in_frame(f, frame)
+    }
+}
+
+impl Default for Catcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{backtrace::BacktraceStatus, panic::AssertUnwindSafe};
+
+    use super::*;
+
+    use crate::{MemoryReporter, PanicLocation, catch};
+
+    /// This test ensures a panic accepted by the filter is caught as usual.
+    #[test]
+    fn catches_accepted_panic() {
+        let catcher = Catcher::new().filter(|info| info.payload().is::<&str>());
+
+        let panic_data = catcher.catch(|| panic!("accepted")).unwrap_err();
+
+        assert_eq!(panic_data.payload_as_string().as_deref(), Some("accepted"));
+    }
+
+    /// A helper macro to store the location of the invocation of this macro in some variable before
+    /// panicking with the given payload.
+    ///
+    /// Relies on the fact that the expansion of the `file!`, `line!`, and `column!` macros will
+    /// occur after the expansion of this macro.
+    macro_rules! panic_any_and_get_location {
+        ($location:ident, $payload:expr) => {
+            $location = ::core::option::Option::Some(PanicLocation {
+                file: String::from(file!()),
+                line: line!(),
+                col: column!(),
+            });
+
+            std::panic::panic_any($payload)
+        };
+    }
+
+    /// This test ensures a panic rejected by the filter is caught by an outer
+    /// [`chillpill::catch`](crate::catch), with its original location.
+    #[test]
+    fn rejected_panic_reaches_outer_catch() {
+        let catcher = Catcher::new().filter(|info| info.payload().is::<&str>());
+
+        let mut location = None;
+        let panic_data = catch(AssertUnwindSafe(|| {
+            let _ = catcher.catch(AssertUnwindSafe(|| {
+                panic_any_and_get_location!(location, 42_u8);
+            }));
+        }))
+        .unwrap_err();
+
+        assert_eq!(*panic_data.payload.downcast::<u8>().unwrap(), 42);
+        assert_eq!(panic_data.location, location);
+    }
+
+    /// This test ensures a catcher's reporter receives the same backtrace as the caught panic,
+    /// captured according to the catcher's backtrace setting.
+    #[test]
+    fn reporter_shares_backtrace() {
+        let reporter = Arc::new(MemoryReporter::new());
+
+        let never = Catcher::new()
+            .never_backtrace()
+            .reporter(Arc::clone(&reporter));
+        let panic_data = never.catch(|| panic!("never")).unwrap_err();
+        assert_eq!(panic_data.backtrace.status(), BacktraceStatus::Disabled);
+        assert_eq!(
+            reporter.take_reports()[0].backtrace.status(),
+            BacktraceStatus::Disabled
+        );
+
+        let always = Catcher::new()
+            .force_backtrace()
+            .reporter(Arc::clone(&reporter));
+        let panic_data = always.catch(|| panic!("always")).unwrap_err();
+        assert_eq!(panic_data.backtrace.status(), BacktraceStatus::Captured);
+        assert_eq!(
+            reporter.take_reports()[0].backtrace.status(),
+            BacktraceStatus::Captured
+        );
+    }
+
+    /// This test ensures a panic rejected by nested filters is caught by the first catch (from the
+    /// inside out) that accepts it.
+    #[test]
+    fn nested_filters() {
+        let only_u8 = Catcher::new().filter(|info| info.payload().is::<u8>());
+        let only_u16 = Catcher::new().filter(|info| info.payload().is::<u16>());
+
+        let result = only_u8.catch(AssertUnwindSafe(|| {
+            only_u16
+                .catch(|| std::panic::panic_any(8_u8))
+                .map_err(|_| "caught by the wrong catch")
+        }));
+
+        assert_eq!(*result.unwrap_err().payload.downcast::<u8>().unwrap(), 8);
+    }
+}
@@ -0,0 +1,328 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+    panic::UnwindSafe,
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    PanicData, PanicLocation, catch_inner, fingerprint::normalize_message,
+    thread_local_catch_stack::CaptureBacktrace,
+};
+
+/// The state of a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CircuitState {
+    /// Calls run normally.
+    Closed,
+
+    /// Calls are short-circuited without running, until the cooldown has passed.
+    Open,
+
+    /// The cooldown has passed, and a single probe call is allowed to run. If it succeeds, the
+    /// breaker closes; if it panics, the breaker opens again.
+    HalfOpen,
+}
+
+/// The error returned by [`CircuitBreaker::call`].
+#[derive(Debug)]
+pub enum CircuitBreakerError {
+    /// The breaker is open, so the call was not run.
+    Open,
+
+    /// The call ran, and panicked.
+    Panicked(PanicData),
+}
+
+impl Display for CircuitBreakerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Open => write!(f, "the circuit breaker is open"),
+            Self::Panicked(panic_data) => match &panic_data.location {
+                Some(location) => write!(f, "the call panicked at {location}"),
+                None => write!(f, "the call panicked"),
+            },
+        }
+    }
+}
+
+impl std::error::Error for CircuitBreakerError {}
+
+/// Identifies a distinct panic: where it happened, and what its message looked like.
+type PanicKey = (Option<PanicLocation>, Option<String>);
+
+/// A circuit breaker that stops calling an unstable component after it panics repeatedly.
+///
+/// Calls are made through [`CircuitBreaker::call`], which runs them within a [`chillpill::catch`].
+/// Once the same panic occurs `threshold` times within `window`, the breaker opens, and further
+/// calls fail immediately with [`CircuitBreakerError::Open`] instead of running. After the cooldown
+/// has passed, the breaker lets a single probe call through: if it succeeds, the breaker closes
+/// again, and if it panics, the breaker stays open for another cooldown.
+///
+/// Panics are counted separately by [`PanicLocation`] and normalized message (with numbers,
+/// addresses, and quoted values stripped), so each distinct bug needs to reach the threshold on its
+/// own to open the breaker.
+///
+/// A circuit breaker is thread-safe, and is typically shared between workers (for example, in an
+/// [`Arc`](std::sync::Arc)).
+///
+/// [`chillpill::catch`]: crate::catch
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: usize,
+    window: Duration,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Debug)]
+enum BreakerState {
+    Closed {
+        recent_panics: HashMap<PanicKey, VecDeque<Instant>>,
+    },
+    Open {
+        since: Instant,
+    },
+    HalfOpen,
+}
+
+impl BreakerState {
+    fn closed() -> Self {
+        Self::Closed {
+            recent_panics: HashMap::new(),
+        }
+    }
+}
+
+impl CircuitBreaker {
+    /// Creates a closed circuit breaker that opens once the same panic occurs `threshold` times
+    /// within `window`. The cooldown defaults to `window`.
+    ///
+    /// A `threshold` of zero is treated as one.
+    pub fn new(threshold: usize, window: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            window,
+            cooldown: window,
+            state: Mutex::new(BreakerState::closed()),
+        }
+    }
+
+    /// Sets how long the breaker stays open before letting a probe call through.
+    #[must_use]
+    pub fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Returns the current state of the breaker.
+    pub fn state(&self) -> CircuitState {
+        match *self.lock_state() {
+            BreakerState::Closed { .. } => CircuitState::Closed,
+            BreakerState::Open { since } if since.elapsed() >= self.cooldown => {
+                CircuitState::HalfOpen
+            }
+            BreakerState::Open { .. } => CircuitState::Open,
+            BreakerState::HalfOpen => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Invokes a closure within a [`chillpill::catch`], unless the breaker is open.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CircuitBreakerError::Open`] without running the closure if the breaker is open (or
+    /// half-open with a probe call already running), and [`CircuitBreakerError::Panicked`] if the
+    /// closure panicked.
+    ///
+    /// # Panics
+    ///
+    /// See [`chillpill::catch`].
+    ///
+    /// [`chillpill::catch`]: crate::catch
+    pub fn call<F: FnOnce() -> R + UnwindSafe, R>(&self, f: F) -> Result<R, CircuitBreakerError> {
+        let probe_guard = {
+            let mut state = self.lock_state();
+            match *state {
+                BreakerState::Closed { .. } => None,
+                BreakerState::Open { since } if since.elapsed() >= self.cooldown => {
+                    *state = BreakerState::HalfOpen;
+                    Some(ProbeGuard { breaker: self })
+                }
+                BreakerState::Open { .. } | BreakerState::HalfOpen => {
+                    return Err(CircuitBreakerError::Open);
+                }
+            }
+        };
+
+        let result = catch_inner(f, CaptureBacktrace::Default);
+        let is_probe = probe_guard.map(ProbeGuard::disarm).is_some();
+
+        let mut state = self.lock_state();
+        match result {
+            Ok(value) => {
+                if is_probe {
+                    *state = BreakerState::closed();
+                }
+
+                Ok(value)
+            }
+            Err(panic_data) => {
+                let now = Instant::now();
+                match &mut *state {
+                    // Only count panics while closed - panics from calls that started before the
+                    // breaker opened don't need to open it again
+                    BreakerState::Closed { recent_panics } if !is_probe => {
+                        let key = (
+                            panic_data.location.clone(),
+                            panic_data
+                                .payload_as_string()
+                                .as_deref()
+                                .map(normalize_message),
+                        );
+
+                        // Forget every panic that has left the window, so that panics which never
+                        // recur don't accumulate
+                        recent_panics.retain(|_, times| {
+                            while times
+                                .front()
+                                .is_some_and(|&time| now.duration_since(time) > self.window)
+                            {
+                                times.pop_front();
+                            }
+
+                            !times.is_empty()
+                        });
+
+                        let times = recent_panics.entry(key).or_default();
+                        times.push_back(now);
+                        if times.len() >= self.threshold {
+                            *state = BreakerState::Open { since: now };
+                        }
+                    }
+                    _ if is_probe => *state = BreakerState::Open { since: now },
+                    _ => {}
+                }
+
+                Err(CircuitBreakerError::Panicked(panic_data))
+            }
+        }
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, BreakerState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Reopens a half-open breaker if its probe call unwinds out of [`CircuitBreaker::call`] (as fatal
+/// panics do), so that the breaker isn't left half-open forever.
+struct ProbeGuard<'a> {
+    breaker: &'a CircuitBreaker,
+}
+
+impl ProbeGuard<'_> {
+    /// Disarms the guard once the probe call has returned.
+    fn disarm(self) {
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for ProbeGuard<'_> {
+    fn drop(&mut self) {
+        *self.breaker.lock_state() = BreakerState::Open {
+            since: Instant::now(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Helper function that panics with a message including `n`, always at the same location.
+    fn panic_with(n: u32) {
+        panic!("request {n} failed");
+    }
+
+    /// This test ensures the breaker opens after `threshold` panics at the same location, and
+    /// short-circuits calls afterwards.
+    #[test]
+    fn opens_after_threshold() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+
+        for n in 0..3 {
+            assert_eq!(breaker.state(), CircuitState::Closed);
+            let err = breaker.call(|| panic_with(n)).unwrap_err();
+            assert!(matches!(err, CircuitBreakerError::Panicked(_)));
+        }
+
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(matches!(
+            breaker.call(|| unreachable!()),
+            Err(CircuitBreakerError::Open)
+        ));
+    }
+
+    /// This test ensures distinct panics are counted separately.
+    #[test]
+    fn distinct_panics_counted_separately() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+
+        breaker.call(|| panic_with(1)).unwrap_err();
+        breaker.call(|| panic!("something else")).unwrap_err();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        breaker.call(|| panic_with(2)).unwrap_err();
+        assert_eq!(breaker.state(), CircuitState::Open);
+    }
+
+    /// This test ensures a successful probe closes the breaker, and a panicking probe reopens it.
+    #[test]
+    fn probe_closes_or_reopens() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60)).cooldown(Duration::ZERO);
+
+        breaker.call(|| panic_with(1)).unwrap_err();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        breaker.call(|| panic_with(2)).unwrap_err();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        assert_eq!(breaker.call(|| 42).unwrap(), 42);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    /// This test ensures panics that leave the window without recurring are forgotten.
+    #[test]
+    fn stale_panics_forgotten() {
+        let breaker = CircuitBreaker::new(10, Duration::from_millis(1));
+
+        breaker.call(|| panic!("once")).unwrap_err();
+        std::thread::sleep(Duration::from_millis(10));
+        breaker.call(|| panic!("twice")).unwrap_err();
+
+        match &*breaker.lock_state() {
+            BreakerState::Closed { recent_panics } => assert_eq!(recent_panics.len(), 1),
+            state => panic!("unexpected state {state:?}"),
+        }
+    }
+
+    /// This test ensures a fatal panic in a probe call reopens the breaker, rather than leaving it
+    /// half-open forever.
+    #[test]
+    fn fatal_panic_in_probe_reopens() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60)).cooldown(Duration::ZERO);
+
+        breaker.call(|| panic_with(1)).unwrap_err();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        let result = std::panic::catch_unwind(|| {
+            let _ = breaker.call(|| crate::panic_fatal!("probe failed"));
+        });
+        assert!(result.unwrap_err().is::<crate::FatalPanic>());
+        assert!(matches!(*breaker.lock_state(), BreakerState::Open { .. }));
+
+        assert_eq!(breaker.call(|| 42).unwrap(), 42);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+}
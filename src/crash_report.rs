@@ -0,0 +1,498 @@
+use std::{
+    backtrace::Backtrace,
+    cell::RefCell,
+    fmt::Write,
+    fs, io,
+    panic::PanicHookInfo,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, PoisonError, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    PanicData, PanicSnapshot, json, panic_data::payload_as_str, panic_hook,
+    panic_report::hook_context,
+};
+
+/// The file name prefix shared by all crash report files. Only files with this prefix are ever
+/// considered for rotation.
+const FILE_PREFIX: &str = "chillpill-crash-";
+
+/// The panic messages the standard library uses for panics that cannot unwind, which it follows up
+/// by immediately aborting the process.
+const NOUNWIND_PANIC_MESSAGES: [&str; 2] = [
+    "panic in a destructor during cleanup",
+    "panic in a function that cannot unwind",
+];
+
+/// The globally installed crash reporter, if any.
+static CRASH_REPORTER: RwLock<Option<Arc<CrashReporter>>> = RwLock::new(None);
+
+thread_local! {
+    /// The two most recent panics on this thread, tracked while a crash reporter is installed so
+    /// that a crash report for a double panic can include the panic that was already in flight.
+    static RECENT_PANICS: RefCell<RecentPanics> = const {
+        RefCell::new(RecentPanics { previous: None, last: None })
+    };
+}
+
+struct RecentPanics {
+    previous: Option<PanicSnapshot>,
+    last: Option<(PanicSnapshot, Vec<(String, String)>)>,
+}
+
+/// The format of crash report files written by a [`CrashReporter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CrashReportFormat {
+    /// A human-readable plain text report, written to a `.txt` file.
+    Text,
+
+    /// A single JSON object, written to a `.json` file.
+    Json,
+}
+
+/// An opt-in reporter that writes a crash report file just before a panic aborts the process.
+///
+/// Once installed (see [`CrashReporter::install`]), a report is written whenever:
+/// - a panic occurs while unwinding from another panic (for example, in a [`Drop`] impl), which
+///   causes the process to abort;
+/// - a panic occurs in a function that cannot unwind, which also causes the process to abort;
+/// - any panic occurs in a program built with `panic = "abort"`;
+/// - the uncaught panic handler returns [`UncaughtAction::Abort`]; or
+/// - [`chillpill::main`] catches a panic in the program's real `main` function.
+///
+/// Each report contains the panic payload, location, thread, context, and a backtrace, as well as
+/// the panic that was already in flight for double panics. Reports are written to one file each in
+/// the configured directory, and the oldest reports are deleted once there are more than
+/// [`max_files`](CrashReporter::max_files) of them.
+///
+/// [`UncaughtAction::Abort`]: crate::UncaughtAction::Abort
+/// [`chillpill::main`]: crate::main
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrashReporter {
+    directory: PathBuf,
+    max_files: usize,
+    format: CrashReportFormat,
+}
+
+impl CrashReporter {
+    /// Creates a crash reporter that writes text reports to `directory`, keeping at most 16 of them.
+    ///
+    /// The directory is created when the first report is written, if it does not already exist.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            max_files: 16,
+            format: CrashReportFormat::Text,
+        }
+    }
+
+    /// Sets the maximum number of crash report files kept in the directory. Once a new report would
+    /// exceed this limit, the oldest reports are deleted.
+    ///
+    /// A limit of zero is treated as one, so the most recent report is always kept.
+    #[must_use]
+    pub fn max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files.max(1);
+        self
+    }
+
+    /// Sets the format of crash report files.
+    #[must_use]
+    pub fn format(mut self, format: CrashReportFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Installs this crash reporter globally, replacing any previously installed crash reporter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the chillpill panic hook is not yet installed and this thread is currently
+    /// unwinding from a panic.
+    pub fn install(self) {
+        if let Err(()) = panic_hook::install_if_not_installed() {
+            panic!("the chillpill panic hook must not be installed from a panicking thread");
+        }
+
+        *CRASH_REPORTER
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(self));
+    }
+
+    /// Writes a crash report to a new file in the directory, then rotates old reports out.
+    fn write(&self, report: &CrashReport<'_>) -> io::Result<PathBuf> {
+        static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+        let (contents, extension) = match self.format {
+            CrashReportFormat::Text => (report.render_text(), "txt"),
+            CrashReportFormat::Json => (report.render_json(), "json"),
+        };
+
+        // File names sort in the order they were written, which rotation relies on
+        let now = unix_time(SystemTime::now());
+        let file_name = format!(
+            "{FILE_PREFIX}{:020}.{:09}-{}-{}.{extension}",
+            now.as_secs(),
+            now.subsec_nanos(),
+            std::process::id(),
+            SEQUENCE.fetch_add(1, Ordering::Relaxed),
+        );
+        let path = self.directory.join(file_name);
+
+        fs::create_dir_all(&self.directory)?;
+        fs::write(&path, contents)?;
+        self.rotate()?;
+
+        Ok(path)
+    }
+
+    /// Deletes the oldest crash report files until at most `max_files` remain.
+    fn rotate(&self) -> io::Result<()> {
+        let mut reports = list_reports(&self.directory)?;
+        reports.sort();
+
+        let excess = reports.len().saturating_sub(self.max_files);
+        for path in &reports[..excess] {
+            fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Uninstalls the global crash reporter (see [`CrashReporter::install`]), returning it if one was
+/// installed.
+pub fn take_crash_reporter() -> Option<CrashReporter> {
+    CRASH_REPORTER
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take()
+        .map(Arc::unwrap_or_clone)
+}
+
+/// Returns the installed crash reporter, if any. The lock is released before returning, so reports
+/// are never written while it is held.
+fn installed_crash_reporter() -> Option<Arc<CrashReporter>> {
+    CRASH_REPORTER
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Observes a panic, writing a crash report if the panic is about to abort the process. Called from
+/// the chillpill panic hook for every panic.
+pub(crate) fn observe_panic(info: &PanicHookInfo<'_>) {
+    let Some(crash_reporter) = installed_crash_reporter() else {
+        return;
+    };
+
+    // The standard library reports a panic that cannot unwind with a second, generic panic. The
+    // panic that actually escaped is the one before it, and (for a double panic) the panic that was
+    // in flight is the one before that.
+    let message = payload_as_str(info.payload());
+    if let Some(reason) = message.filter(|message| NOUNWIND_PANIC_MESSAGES.contains(message)) {
+        let (last, previous) =
+            RECENT_PANICS.with_borrow(|recent| (recent.last.clone(), recent.previous.clone()));
+        let (panic, context) =
+            last.unwrap_or_else(|| (PanicSnapshot::from_hook_info(info), hook_context(info)));
+
+        let report = CrashReport {
+            reason: reason.to_string(),
+            panic,
+            context,
+            previous_panic: previous.filter(|_| reason == NOUNWIND_PANIC_MESSAGES[0]),
+            backtrace: &Backtrace::force_capture(),
+        };
+        let _ = crash_reporter.write(&report);
+
+        return;
+    }
+
+    let snapshot = PanicSnapshot::from_hook_info(info);
+    let context = hook_context(info);
+    RECENT_PANICS.with_borrow_mut(|recent| {
+        recent.previous = recent
+            .last
+            .replace((snapshot.clone(), context.clone()))
+            .map(|(previous, _)| previous);
+    });
+
+    if cfg!(panic = "abort") {
+        let report = CrashReport {
+            reason: String::from("panic with `panic = \"abort\"`"),
+            panic: snapshot,
+            context,
+            previous_panic: None,
+            backtrace: &Backtrace::force_capture(),
+        };
+        let _ = crash_reporter.write(&report);
+    }
+}
+
+/// Writes a crash report for a panic that chillpill is about to abort the process for, if a crash
+/// reporter is installed.
+pub(crate) fn report_abort(info: &PanicHookInfo<'_>, reason: &str) {
+    let Some(crash_reporter) = installed_crash_reporter() else {
+        return;
+    };
+
+    let report = CrashReport {
+        reason: reason.to_string(),
+        panic: PanicSnapshot::from_hook_info(info),
+        context: hook_context(info),
+        previous_panic: None,
+        backtrace: &Backtrace::force_capture(),
+    };
+    let _ = crash_reporter.write(&report);
+}
+
+/// Writes a crash report for a panic that was caught, but is about to end the process anyway.
+///
+/// Returns the path of the written report, or [`None`] if no crash reporter is installed or the
+/// report could not be written.
+pub(crate) fn report_caught_panic(panic_data: &PanicData, reason: &str) -> Option<PathBuf> {
+    let crash_reporter = installed_crash_reporter()?;
+
+    let mut context = panic_data.context().to_vec();
+    context.extend_from_slice(panic_data.fields());
+
+    let report = CrashReport {
+        reason: reason.to_string(),
+        panic: PanicSnapshot::from_panic_data(panic_data),
+        context,
+        previous_panic: None,
+        backtrace: &panic_data.backtrace,
+    };
+
+    crash_reporter.write(&report).ok()
+}
+
+/// Lists all crash report files in `directory`.
+fn list_reports(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut reports = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with(FILE_PREFIX) {
+            reports.push(entry.path());
+        }
+    }
+
+    Ok(reports)
+}
+
+/// Returns the time since the Unix epoch, or zero for times before it.
+pub(crate) fn unix_time(time: SystemTime) -> Duration {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default()
+}
+
+/// The contents of a single crash report.
+struct CrashReport<'a> {
+    /// Why the process is about to abort.
+    reason: String,
+
+    /// The panic that is bringing the process down.
+    panic: PanicSnapshot,
+
+    /// Key-value context about `panic`, followed by the fields of a structured payload (see
+    /// [`PanicReport::context`](crate::PanicReport::context)).
+    context: Vec<(String, String)>,
+
+    /// The panic that was already in flight when `panic` occurred, for double panics.
+    previous_panic: Option<PanicSnapshot>,
+
+    /// A backtrace of the panic.
+    backtrace: &'a Backtrace,
+}
+
+impl CrashReport<'_> {
+    fn render_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "chillpill crash report");
+        let _ = writeln!(out, "reason: {}", self.reason);
+        let _ = writeln!(out);
+        let _ = writeln!(out, "panic:");
+        write_snapshot_text(&mut out, &self.panic);
+        if !self.context.is_empty() {
+            let _ = writeln!(out, "  context:");
+            for (key, value) in &self.context {
+                let _ = writeln!(out, "    {key}: {value}");
+            }
+        }
+        if let Some(previous_panic) = &self.previous_panic {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "previous in-flight panic:");
+            write_snapshot_text(&mut out, previous_panic);
+        }
+        let _ = writeln!(out);
+        let _ = writeln!(out, "backtrace:");
+        let _ = writeln!(out, "{}", self.backtrace);
+
+        out
+    }
+
+    fn render_json(&self) -> String {
+        let mut out = String::from("{\"reason\":");
+        json::write_string(&mut out, &self.reason);
+        out.push_str(",\"panic\":");
+        write_snapshot_json(&mut out, &self.panic);
+        out.push_str(",\"context\":");
+        json::write_string_map(&mut out, &self.context);
+        out.push_str(",\"previous_panic\":");
+        match &self.previous_panic {
+            Some(previous_panic) => write_snapshot_json(&mut out, previous_panic),
+            None => out.push_str("null"),
+        }
+        out.push_str(",\"backtrace\":");
+        json::write_string(&mut out, &self.backtrace.to_string());
+        out.push_str("}\n");
+
+        out
+    }
+}
+
+/// Appends a human-readable description of `snapshot` to `out`, one indented line per field.
+pub(crate) fn write_snapshot_text(out: &mut String, snapshot: &PanicSnapshot) {
+    let time = unix_time(snapshot.timestamp);
+    let _ = writeln!(
+        out,
+        "  message: {}",
+        snapshot.message.as_deref().unwrap_or("Box<dyn Any>")
+    );
+    match &snapshot.location {
+        Some(location) => {
+            let _ = writeln!(out, "  location: {location}");
+        }
+        None => {
+            let _ = writeln!(out, "  location: unknown");
+        }
+    }
+    let _ = writeln!(
+        out,
+        "  thread: {} ({:?})",
+        snapshot.thread.name.as_deref().unwrap_or("<unnamed>"),
+        snapshot.thread.id
+    );
+    let _ = writeln!(
+        out,
+        "  time: {}.{:03} (seconds since the Unix epoch)",
+        time.as_secs(),
+        time.subsec_millis()
+    );
+}
+
+fn write_snapshot_json(out: &mut String, snapshot: &PanicSnapshot) {
+    let time = unix_time(snapshot.timestamp);
+    out.push_str("{\"message\":");
+    json::write_optional_string(out, snapshot.message.as_deref());
+    out.push_str(",\"location\":");
+    match &snapshot.location {
+        Some(location) => {
+            out.push_str("{\"file\":");
+            json::write_string(out, &location.file);
+            let _ = write!(
+                out,
+                ",\"line\":{},\"col\":{}}}",
+                location.line, location.col
+            );
+        }
+        None => out.push_str("null"),
+    }
+    out.push_str(",\"thread\":{\"name\":");
+    json::write_optional_string(out, snapshot.thread.name.as_deref());
+    out.push_str(",\"id\":");
+    json::write_string(out, &format!("{:?}", snapshot.thread.id));
+    let _ = write!(
+        out,
+        "}},\"timestamp\":{}.{:03}}}",
+        time.as_secs(),
+        time.subsec_millis()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{PanicLocation, ThreadInfo};
+
+    static DISABLED_BACKTRACE: Backtrace = Backtrace::disabled();
+
+    /// Helper function to create a crash report with fixed contents.
+    fn make_report() -> CrashReport<'static> {
+        CrashReport {
+            reason: String::from("panic in a destructor during cleanup"),
+            panic: PanicSnapshot {
+                message: Some(String::from("in \"drop\"")),
+                location: Some(PanicLocation {
+                    file: String::from("src/main.rs"),
+                    line: 2,
+                    col: 39,
+                }),
+                thread: ThreadInfo::from(&std::thread::current()),
+                timestamp: UNIX_EPOCH + Duration::from_millis(1_500),
+            },
+            context: vec![(String::from("request_id"), String::from("42"))],
+            previous_panic: None,
+            backtrace: &DISABLED_BACKTRACE,
+        }
+    }
+
+    /// Helper function to create a fresh, empty directory for crash reports.
+    fn make_directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("chillpill-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+
+        directory
+    }
+
+    /// This test ensures JSON crash reports escape the payload, include the context, and render
+    /// missing fields as `null`.
+    #[test]
+    fn renders_json() {
+        let json = make_report().render_json();
+
+        assert!(json.starts_with(
+            r#"{"reason":"panic in a destructor during cleanup","panic":{"message":"in \"drop\"","location":{"file":"src/main.rs","line":2,"col":39},"thread":"#
+        ));
+        assert!(json.contains(
+            r#""timestamp":1.500},"context":{"request_id":"42"},"previous_panic":null,"backtrace":"#
+        ));
+    }
+
+    /// This test ensures text crash reports include the reason, payload, location, and context.
+    #[test]
+    fn renders_text() {
+        let text = make_report().render_text();
+
+        assert!(text.contains("reason: panic in a destructor during cleanup\n"));
+        assert!(text.contains("  message: in \"drop\"\n"));
+        assert!(text.contains("  location: src/main.rs:2:39\n"));
+        assert!(text.contains("  context:\n    request_id: 42\n"));
+        assert!(!text.contains("previous in-flight panic:"));
+    }
+
+    /// This test ensures that writing more reports than `max_files` deletes the oldest ones.
+    #[test]
+    fn rotates_old_reports() {
+        let directory = make_directory("rotates-old-reports");
+        let crash_reporter = CrashReporter::new(&directory)
+            .max_files(2)
+            .format(CrashReportFormat::Json);
+
+        let first = crash_reporter.write(&make_report()).unwrap();
+        let second = crash_reporter.write(&make_report()).unwrap();
+        let third = crash_reporter.write(&make_report()).unwrap();
+
+        let mut reports = list_reports(&directory).unwrap();
+        reports.sort();
+        assert_eq!(reports, [second, third]);
+        assert!(!first.exists());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,181 @@
+use std::{
+    any::Any,
+    backtrace::Backtrace,
+    cell::OnceCell,
+    fmt::Display,
+    panic::PanicHookInfo,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use crate::{panic_hook, thread_local_catch_stack::THREAD_LOCAL_CATCH_STACK, uncaught};
+
+/// Whether fatal panics abort the process once they have been reported.
+static ABORT_ON_FATAL_PANIC: AtomicBool = AtomicBool::new(false);
+
+/// The payload of a fatal panic, which no [`chillpill::catch`] will catch.
+///
+/// Some invariant violations must bring the process down, even when a library somewhere up the
+/// stack wraps everything in a [`chillpill::catch`]. Panicking with this payload (usually via
+/// [`panic_fatal!`](crate::panic_fatal)) produces a panic that every chillpill catch (including
+/// [`Catcher`](crate::Catcher), [`retry`](crate::retry()), [`Supervisor`](crate::Supervisor), and
+/// [`CatchGroup`](crate::CatchGroup)) lets keep unwinding, all the way up to the top of the thread.
+///
+/// Fatal panics are never caught by chillpill, so they are handled like any other uncaught panic
+/// (see [`set_uncaught_handler`](crate::set_uncaught_handler)). If
+/// [`set_abort_on_fatal_panic`] is enabled, the process is then aborted after writing a crash
+/// report (see [`CrashReporter`](crate::CrashReporter)).
+///
+/// Note that the standard library's default panic hook only prints messages of `&str` and `String`
+/// payloads, so it prints this payload as `Box<dyn Any>`. The message is available to the uncaught
+/// panic handler through [`PanicReport::message`](crate::PanicReport::message).
+/// [`std::panic::catch_unwind`] callers can downcast the payload to `FatalPanic` and call
+/// [`message`](FatalPanic::message).
+///
+/// [`chillpill::catch`]: crate::catch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FatalPanic {
+    message: String,
+}
+
+impl FatalPanic {
+    /// Creates a fatal panic payload with the given message.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+
+    /// Returns the message of the fatal panic.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for FatalPanic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "fatal panic: {}", self.message)
+    }
+}
+
+/// Panics with a [`FatalPanic`] payload, which no [`chillpill::catch`] will catch.
+///
+/// Takes the same arguments as [`panic!`], and formats them into the payload's message.
+///
+/// # Example
+///
+/// ```
+/// let result = std::panic::catch_unwind(|| {
+///     let _ = chillpill::catch(|| {
+///         chillpill::panic_fatal!("invariant violated: {}", 42);
+///     });
+///
+///     unreachable!("the fatal panic keeps unwinding past `chillpill::catch`");
+/// });
+///
+/// let payload = result.unwrap_err();
+/// let fatal_panic = payload.downcast_ref::<chillpill::FatalPanic>().unwrap();
+/// assert_eq!(fatal_panic.message(), "invariant violated: 42");
+/// ```
+///
+/// [`chillpill::catch`]: crate::catch
+#[macro_export]
+macro_rules! panic_fatal {
+    () => {
+        ::std::panic::panic_any($crate::FatalPanic::new("explicit fatal panic"))
+    };
+    ($($arg:tt)+) => {
+        ::std::panic::panic_any($crate::FatalPanic::new(::std::format!($($arg)+)))
+    };
+}
+
+/// Sets whether fatal panics (see [`FatalPanic`]) abort the process.
+///
+/// When enabled, fatal panics are handled like any other uncaught panic, then a crash report is
+/// written and the process is aborted. By default, they just keep unwinding like any other panic.
+///
+/// # Panics
+///
+/// Panics if the chillpill panic hook is not yet installed and this thread is currently unwinding
+/// from a panic.
+pub fn set_abort_on_fatal_panic(abort: bool) {
+    if let Err(()) = panic_hook::install_if_not_installed() {
+        panic!("the chillpill panic hook must not be installed from a panicking thread");
+    }
+
+    ABORT_ON_FATAL_PANIC.store(abort, Ordering::Relaxed);
+}
+
+/// Returns whether a panic payload is a [`FatalPanic`].
+pub(crate) fn is_fatal(payload: &(dyn Any + Send)) -> bool {
+    payload.is::<FatalPanic>()
+}
+
+/// Handles a fatal panic. Called from the chillpill panic hook.
+///
+/// `backtrace` is the backtrace of the panic, captured on first use (see [`uncaught::backtrace`]).
+pub(crate) fn handle_fatal_panic(info: &PanicHookInfo<'_>, backtrace: &OnceCell<Arc<Backtrace>>) {
+    // Mark every frame as having rejected the panic, so that every `chillpill::catch` lets it keep
+    // unwinding
+    THREAD_LOCAL_CATCH_STACK.with_borrow_mut(|stack| {
+        for frame in stack.iter_mut() {
+            frame.rejected = true;
+        }
+    });
+
+    uncaught::handle_uncaught_panic(info, backtrace);
+
+    if ABORT_ON_FATAL_PANIC.load(Ordering::Relaxed) {
+        uncaught::abort(info, "fatal panic");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::AssertUnwindSafe;
+
+    use super::*;
+
+    use crate::{Catcher, catch};
+
+    /// This test ensures a fatal panic is not caught by nested catches, and keeps unwinding with its
+    /// original payload.
+    #[test]
+    fn passes_through_nested_catches() {
+        let result = std::panic::catch_unwind(|| {
+            let _ = catch(|| {
+                let _ = Catcher::new()
+                    .filter(|_| true)
+                    .catch(|| panic_fatal!("invariant {} violated", 7));
+            });
+        });
+
+        let payload = result.unwrap_err();
+        assert_eq!(
+            payload.downcast_ref::<FatalPanic>(),
+            Some(&FatalPanic::new("invariant 7 violated"))
+        );
+    }
+
+    /// This test ensures a fatal panic resumed without invoking the panic hook is not caught
+    /// either.
+    #[test]
+    fn resumed_fatal_panic_passes_through() {
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            let _ = catch(|| std::panic::resume_unwind(Box::new(FatalPanic::new("resumed"))));
+        }));
+
+        assert!(result.unwrap_err().is::<FatalPanic>());
+    }
+
+    /// This test ensures the message of a fatal panic is reported as its payload string.
+    #[test]
+    fn message_is_payload_string() {
+        assert_eq!(
+            crate::panic_data::payload_as_str(&FatalPanic::new("message")),
+            Some("message")
+        );
+    }
+}
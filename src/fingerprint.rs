@@ -0,0 +1,339 @@
+//! Fingerprinting of panics, so that panics caused by the same bug can be grouped together.
+
+use std::{
+    backtrace::{Backtrace, BacktraceStatus},
+    fmt::Display,
+    path::Path,
+};
+
+use crate::PanicLocation;
+
+/// A stable identifier for "the same bug", computed from a panic's location, normalized message,
+/// and optionally its backtrace.
+///
+/// See [`PanicData::fingerprint`] and [`PanicGrouper`].
+///
+/// Fingerprints are stable: the same inputs produce the same fingerprint across runs, platforms,
+/// and versions of chillpill and Rust, so they can be stored and compared later. Path separators
+/// in the location are normalized, so a panic fingerprints the same on Windows as elsewhere. They
+/// display as 16 lowercase hexadecimal digits.
+///
+/// [`PanicData::fingerprint`]: crate::PanicData::fingerprint
+/// [`PanicGrouper`]: crate::PanicGrouper
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fingerprint(pub u64);
+
+impl Display for Fingerprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl Fingerprint {
+    /// Computes the fingerprint of a panic from its location, message, and backtrace frames.
+    ///
+    /// The message is normalized first (see [`normalize_message`]).
+    pub(crate) fn compute(
+        location: Option<&PanicLocation>,
+        message: Option<&str>,
+        frames: &[String],
+    ) -> Self {
+        let mut hasher = Fnv1a::new();
+
+        match location {
+            Some(location) => {
+                hasher.write(&[1]);
+                hasher.write_field(location.file.replace('\\', "/").as_bytes());
+                hasher.write_field(&location.line.to_le_bytes());
+                hasher.write_field(&location.col.to_le_bytes());
+            }
+            None => hasher.write(&[0]),
+        }
+        match message {
+            Some(message) => {
+                hasher.write(&[1]);
+                hasher.write_field(normalize_message(message).as_bytes());
+            }
+            None => hasher.write(&[0]),
+        }
+        for frame in frames {
+            hasher.write_field(frame.as_bytes());
+        }
+
+        Self(hasher.finish())
+    }
+}
+
+/// The 64-bit FNV-1a hash function, which (unlike [`std::hash::DefaultHasher`]) is guaranteed to
+/// be stable.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    /// Writes a length-prefixed field, so that adjacent fields can't run into each other.
+    fn write_field(&mut self, bytes: &[u8]) {
+        self.write(&(bytes.len() as u64).to_le_bytes());
+        self.write(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Returns the symbols of (at most) the top `max_frames` frames of `backtrace` that are in the
+/// user's crates, below the panic machinery.
+///
+/// A frame is considered in-crate if its source file path is relative. The standard library prints
+/// paths within the current directory (that is, the workspace being run) as relative paths, and
+/// all others (the standard library's own sources, and dependencies in the cargo registry) as
+/// absolute paths.
+///
+/// Returns no frames if the backtrace was not captured.
+pub(crate) fn in_crate_frames(backtrace: &Backtrace, max_frames: usize) -> Vec<String> {
+    if max_frames == 0 || backtrace.status() != BacktraceStatus::Captured {
+        return Vec::new();
+    }
+
+    in_crate_frames_from_text(&backtrace.to_string(), max_frames)
+}
+
+/// Parses the [`Display`] output of a [`Backtrace`] into its frames, as pairs of a symbol and an
+/// optional source location (like `"src/main.rs:3:5"`).
+pub(crate) fn parse_frames(backtrace: &str) -> Vec<(&str, Option<&str>)> {
+    // Each frame is a line with its index and symbol, optionally followed by a line with its source
+    // location
+    let mut frames: Vec<(&str, Option<&str>)> = Vec::new();
+    for line in backtrace.lines() {
+        let line = line.trim_start();
+        if let Some(path) = line.strip_prefix("at ") {
+            if let Some((_, frame_path)) = frames.last_mut() {
+                *frame_path = Some(path);
+            }
+        } else if let Some((index, symbol)) = line.split_once(": ") {
+            if index.bytes().all(|byte| byte.is_ascii_digit()) {
+                frames.push((symbol, None));
+            }
+        }
+    }
+
+    frames
+}
+
+/// Like [`parse_frames`], but skipping the panic hook and the panic machinery, which start the
+/// backtrace of a panic.
+pub(crate) fn parse_frames_below_panic(backtrace: &str) -> Vec<(&str, Option<&str>)> {
+    let mut frames = parse_frames(backtrace);
+
+    let start = frames
+        .iter()
+        .rposition(|(symbol, _)| {
+            symbol.contains("rust_begin_unwind")
+                || symbol.starts_with("std::panicking::begin_panic")
+        })
+        .map_or(0, |index| index + 1);
+    frames.drain(..start);
+
+    frames
+}
+
+/// Returns whether a backtrace frame's source path is in the user's crates (see
+/// [`in_crate_frames`]).
+pub(crate) fn is_in_crate_path(path: &str) -> bool {
+    Path::new(path).is_relative()
+}
+
+/// Like [`in_crate_frames`], but parsing the [`Display`] output of a [`Backtrace`].
+fn in_crate_frames_from_text(backtrace: &str, max_frames: usize) -> Vec<String> {
+    parse_frames_below_panic(backtrace)
+        .iter()
+        .filter(|(_, path)| path.is_some_and(is_in_crate_path))
+        .take(max_frames)
+        .map(|(symbol, _)| (*symbol).to_string())
+        .collect()
+}
+
+/// Normalizes a panic message into a template, so that messages from the same panic site compare
+/// equal even when they include varying values.
+///
+/// Specifically:
+/// - quoted values (in `"double"`, `'single'`, or `` `backtick` `` quotes) become `<str>`;
+/// - hexadecimal numbers (like addresses, e.g. `0x7ffd5fbff8a0`) become `<hex>`; and
+/// - decimal numbers become `<num>`.
+pub(crate) fn normalize_message(message: &str) -> String {
+    let mut out = String::with_capacity(message.len());
+    let mut chars = message.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '"' | '\'' | '`' => {
+                // Only treat this as a quote if it is closed on the same line
+                let closing = message[start + 1..]
+                    .find([c, '\n'])
+                    .filter(|&offset| message[start + 1 + offset..].starts_with(c));
+                match closing {
+                    Some(offset) => {
+                        out.push_str("<str>");
+                        let end = start + 1 + offset;
+                        while chars.next_if(|&(i, _)| i <= end).is_some() {}
+                    }
+                    None => out.push(c),
+                }
+            }
+            '0' if chars
+                .peek()
+                .is_some_and(|&(_, next)| next == 'x' || next == 'X') =>
+            {
+                chars.next();
+                while chars.next_if(|(_, c)| c.is_ascii_hexdigit()).is_some() {}
+                out.push_str("<hex>");
+            }
+            c if c.is_ascii_digit() => {
+                while chars
+                    .next_if(|(_, c)| c.is_ascii_digit() || *c == '.' || *c == '_')
+                    .is_some()
+                {}
+                out.push_str("<num>");
+            }
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Catcher, PanicData};
+
+    /// This test ensures numbers, addresses, and quoted values are replaced with placeholders.
+    #[test]
+    fn normalizes_varying_values() {
+        assert_eq!(
+            normalize_message("index out of bounds: the len is 3 but the index is 10"),
+            "index out of bounds: the len is <num> but the index is <num>"
+        );
+        assert_eq!(
+            normalize_message("bad pointer 0x7ffd5fbff8a0 (took 1.5s)"),
+            "bad pointer <hex> (took <num>s)"
+        );
+        assert_eq!(
+            normalize_message(r#"called `Result::unwrap()` on an `Err` value: "no such user""#),
+            "called <str> on an <str> value: <str>"
+        );
+    }
+
+    /// This test ensures unclosed quotes (like apostrophes) are left alone.
+    #[test]
+    fn leaves_unclosed_quotes() {
+        assert_eq!(normalize_message("can't open file"), "can't open file");
+        assert_eq!(
+            normalize_message("can't find \"x\n\" y"),
+            "can't find \"x\n\" y"
+        );
+    }
+
+    /// This test ensures fingerprints are stable across runs and versions.
+    #[test]
+    fn fingerprint_is_stable() {
+        let location = PanicLocation {
+            file: String::from("src/main.rs"),
+            line: 1,
+            col: 2,
+        };
+
+        assert_eq!(
+            Fingerprint::compute(Some(&location), Some("failed 3 times"), &[]).to_string(),
+            "d8441f53b1df6193"
+        );
+        assert_eq!(
+            Fingerprint::compute(Some(&location), Some("failed 3 times"), &[]),
+            Fingerprint::compute(Some(&location), Some("failed 4 times"), &[])
+        );
+        assert_ne!(
+            Fingerprint::compute(Some(&location), Some("failed"), &[]),
+            Fingerprint::compute(Some(&location), None, &[])
+        );
+    }
+
+    /// This test ensures fingerprints do not depend on the platform's path separator.
+    #[test]
+    fn fingerprint_normalizes_path_separators() {
+        let location = |file: &str| PanicLocation {
+            file: String::from(file),
+            line: 1,
+            col: 2,
+        };
+
+        assert_eq!(
+            Fingerprint::compute(Some(&location("src\\main.rs")), Some("failed"), &[]),
+            Fingerprint::compute(Some(&location("src/main.rs")), Some("failed"), &[])
+        );
+    }
+
+    /// This test ensures in-crate frames are found below the panic machinery.
+    #[test]
+    fn parses_in_crate_frames() {
+        let backtrace = "   0: app::hook
+             at ./src/hook.rs:3:49
+   1: __rustc::rust_begin_unwind
+             at /rustc/0123/library/std/src/panicking.rs:689:5
+   2: core::panicking::panic_fmt
+             at /rustc/0123/library/core/src/panicking.rs:80:14
+   3: dependency::helper
+             at /home/user/.cargo/registry/src/dependency/src/lib.rs:1:1
+   4: app::inner
+             at ./src/main.rs:1:48
+   5: __rust_try
+   6: app::main
+             at ./src/main.rs:4:13
+";
+
+        assert_eq!(
+            in_crate_frames_from_text(backtrace, 8),
+            ["app::inner", "app::main"]
+        );
+        assert_eq!(in_crate_frames_from_text(backtrace, 1), ["app::inner"]);
+    }
+
+    /// Helper function that always panics at the same location.
+    fn panic_here() {
+        panic!("always here");
+    }
+
+    fn caller_a() {
+        panic_here();
+    }
+
+    fn caller_b() {
+        panic_here();
+    }
+
+    /// This test ensures backtrace frames distinguish panics at the same location with different
+    /// callers.
+    #[test]
+    fn frames_distinguish_callers() {
+        let catcher = Catcher::new().force_backtrace();
+        let a: PanicData = catcher.catch(caller_a).unwrap_err();
+        let b: PanicData = catcher.catch(caller_b).unwrap_err();
+
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        assert_ne!(a.fingerprint_with_frames(2), b.fingerprint_with_frames(2));
+        assert_eq!(a.fingerprint_with_frames(0), a.fingerprint());
+    }
+}
@@ -0,0 +1,84 @@
+use std::{
+    collections::VecDeque,
+    panic::PanicHookInfo,
+    sync::{
+        Mutex, PoisonError,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use crate::{PanicSnapshot, panic_hook};
+
+/// The maximum number of panics kept by the flight recorder.
+static CAPACITY: AtomicUsize = AtomicUsize::new(16);
+
+/// The most recent panics seen by the chillpill panic hook, oldest first.
+static RECENT_PANICS: Mutex<VecDeque<PanicSnapshot>> = Mutex::new(VecDeque::new());
+
+/// Returns snapshots of the most recent panics in the process, oldest first.
+///
+/// The chillpill panic hook keeps a bounded "flight recorder" of every panic it sees on any thread,
+/// whether it was caught by [`chillpill::catch`], by [`std::panic::catch_unwind`], or not at all.
+/// This is useful for post-mortem debugging: when something fails strangely, it shows which panics
+/// were swallowed nearby.
+///
+/// By default, the 16 most recent panics are kept (see [`set_recent_panics_capacity`]). Panics
+/// that occur before the chillpill panic hook is installed (by the first call to `chillpill::catch`,
+/// or to a function like [`set_recent_panics_capacity`]) are not recorded.
+///
+/// # Example
+///
+/// ```
+/// chillpill::catch(|| panic!("swallowed")).unwrap_err();
+///
+/// let recent_panics = chillpill::recent_panics();
+/// assert_eq!(
+///     recent_panics.last().unwrap().message.as_deref(),
+///     Some("swallowed")
+/// );
+/// ```
+///
+/// [`chillpill::catch`]: crate::catch
+pub fn recent_panics() -> Vec<PanicSnapshot> {
+    RECENT_PANICS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .cloned()
+        .collect()
+}
+
+/// Sets the maximum number of panics kept for [`recent_panics`], discarding the oldest panics if
+/// more are already kept. A capacity of zero disables the flight recorder.
+///
+/// # Panics
+///
+/// Panics if the chillpill panic hook is not yet installed and this thread is currently unwinding
+/// from a panic.
+pub fn set_recent_panics_capacity(capacity: usize) {
+    if let Err(()) = panic_hook::install_if_not_installed() {
+        panic!("the chillpill panic hook must not be installed from a panicking thread");
+    }
+
+    let mut recent_panics = RECENT_PANICS.lock().unwrap_or_else(PoisonError::into_inner);
+    CAPACITY.store(capacity, Ordering::Relaxed);
+    truncate_front(&mut recent_panics, capacity);
+}
+
+/// Records a panic in the flight recorder. Called from the chillpill panic hook.
+pub(crate) fn record_panic(info: &PanicHookInfo<'_>) {
+    if CAPACITY.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+
+    let snapshot = PanicSnapshot::from_hook_info(info);
+    let mut recent_panics = RECENT_PANICS.lock().unwrap_or_else(PoisonError::into_inner);
+    recent_panics.push_back(snapshot);
+    truncate_front(&mut recent_panics, CAPACITY.load(Ordering::Relaxed));
+}
+
+/// Discards the oldest panics until at most `capacity` remain.
+fn truncate_front(recent_panics: &mut VecDeque<PanicSnapshot>, capacity: usize) {
+    let excess = recent_panics.len().saturating_sub(capacity);
+    recent_panics.drain(..excess);
+}
@@ -0,0 +1,67 @@
+//! Minimal helpers for writing JSON by hand, so that chillpill does not need a JSON dependency.
+
+use std::fmt::Write;
+
+/// Appends `s` to `out` as a quoted, escaped JSON string.
+pub fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Appends `s` to `out` as a quoted, escaped JSON string, or `null` if `s` is [`None`].
+pub fn write_optional_string(out: &mut String, s: Option<&str>) {
+    match s {
+        Some(s) => write_string(out, s),
+        None => out.push_str("null"),
+    }
+}
+
+/// Appends key-value pairs to `out` as a JSON object mapping keys to string values.
+pub fn write_string_map(out: &mut String, pairs: &[(String, String)]) {
+    out.push('{');
+    for (index, (key, value)) in pairs.iter().enumerate() {
+        if index > 0 {
+            out.push(',');
+        }
+        write_string(out, key);
+        out.push(':');
+        write_string(out, value);
+    }
+    out.push('}');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// This test ensures [`write_string`] escapes quotes, backslashes, and control characters.
+    #[test]
+    fn escapes_special_characters() {
+        let mut out = String::new();
+        write_string(&mut out, "a \"quoted\" \\ line\nbreak\u{1}");
+
+        assert_eq!(out, r#""a \"quoted\" \\ line\nbreak\u0001""#);
+    }
+
+    /// This test ensures [`write_optional_string`] writes `null` for [`None`].
+    #[test]
+    fn writes_null_for_none() {
+        let mut out = String::new();
+        write_optional_string(&mut out, None);
+
+        assert_eq!(out, "null");
+    }
+}
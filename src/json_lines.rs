@@ -0,0 +1,288 @@
+//! The JSON Lines schema of a [`PanicReport`] (see [`PanicReport::to_json_line`]).
+
+use std::{backtrace::BacktraceStatus, fmt::Write as _, time::SystemTime};
+
+use crate::{PanicReport, crash_report::unix_time, fingerprint::parse_frames, json};
+
+/// The version of the JSON Lines schema written by [`PanicReport::to_json_line`].
+///
+/// Members may be added to the schema without changing its version, but any other change (like
+/// removing, renaming, or retyping a member) increments it.
+pub const JSON_LINES_SCHEMA_VERSION: u32 = 1;
+
+/// Returns the number of milliseconds since the Unix epoch, or zero for times before it.
+fn timestamp_millis(time: SystemTime) -> u64 {
+    u64::try_from(unix_time(time).as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Returns the [`Display`](std::fmt::Display) output of the report's backtrace, or [`None`] if it
+/// was not captured.
+fn backtrace_text(report: &PanicReport) -> Option<String> {
+    (report.backtrace.status() == BacktraceStatus::Captured).then(|| report.backtrace.to_string())
+}
+
+/// Appends `report` to `out` as a JSON object in the schema documented on
+/// [`PanicReport::to_json_line`], without a trailing newline.
+pub(crate) fn write_report(out: &mut String, report: &PanicReport) {
+    let _ = write!(
+        out,
+        "{{\"schema_version\":{JSON_LINES_SCHEMA_VERSION},\"timestamp_ms\":{}",
+        timestamp_millis(report.timestamp)
+    );
+
+    out.push_str(",\"thread\":{\"name\":");
+    json::write_optional_string(out, report.thread.name.as_deref());
+    out.push_str(",\"id\":");
+    json::write_string(out, &format!("{:?}", report.thread.id));
+    out.push('}');
+
+    out.push_str(",\"payload\":");
+    json::write_optional_string(out, report.message.as_deref());
+    out.push_str(",\"payload_type\":");
+    json::write_optional_string(out, report.payload_type);
+
+    out.push_str(",\"location\":");
+    match &report.location {
+        Some(location) => {
+            out.push_str("{\"file\":");
+            json::write_string(out, &location.file);
+            let _ = write!(
+                out,
+                ",\"line\":{},\"col\":{}}}",
+                location.line, location.col
+            );
+        }
+        None => out.push_str("null"),
+    }
+
+    out.push_str(",\"backtrace\":");
+    match backtrace_text(report) {
+        Some(backtrace) => write_frames(out, &backtrace),
+        None => out.push_str("null"),
+    }
+
+    out.push_str(",\"context\":");
+    json::write_string_map(out, &report.context);
+    out.push_str(",\"tags\":");
+    json::write_string_map(out, &report.tags);
+
+    let _ = write!(out, ",\"fingerprint\":\"{}\"}}", report.fingerprint());
+}
+
+/// Appends the frames of a backtrace (in its [`Display`](std::fmt::Display) output) to `out` as a
+/// JSON array.
+fn write_frames(out: &mut String, backtrace: &str) {
+    out.push('[');
+    for (index, (symbol, location)) in parse_frames(backtrace).into_iter().enumerate() {
+        if index > 0 {
+            out.push(',');
+        }
+        out.push_str("{\"symbol\":");
+        json::write_string(out, symbol);
+        out.push_str(",\"location\":");
+        json::write_optional_string(out, location);
+        out.push('}');
+    }
+    out.push(']');
+}
+
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use serde::{
+        Serialize, Serializer,
+        ser::{SerializeMap, SerializeSeq, SerializeStruct},
+    };
+
+    use super::{JSON_LINES_SCHEMA_VERSION, backtrace_text, parse_frames, timestamp_millis};
+    use crate::{Fingerprint, PanicLocation, PanicReport, ThreadInfo};
+
+    impl Serialize for PanicReport {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let backtrace = backtrace_text(self);
+
+            let mut report = serializer.serialize_struct("PanicReport", 10)?;
+            report.serialize_field("schema_version", &JSON_LINES_SCHEMA_VERSION)?;
+            report.serialize_field("timestamp_ms", &timestamp_millis(self.timestamp))?;
+            report.serialize_field("thread", &self.thread)?;
+            report.serialize_field("payload", &self.message)?;
+            report.serialize_field("payload_type", &self.payload_type)?;
+            report.serialize_field("location", &self.location)?;
+            report.serialize_field("backtrace", &backtrace.as_deref().map(Frames))?;
+            report.serialize_field("context", &Pairs(&self.context))?;
+            report.serialize_field("tags", &Pairs(&self.tags))?;
+            report.serialize_field("fingerprint", &self.fingerprint())?;
+            report.end()
+        }
+    }
+
+    impl Serialize for ThreadInfo {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut thread = serializer.serialize_struct("ThreadInfo", 2)?;
+            thread.serialize_field("name", &self.name)?;
+            thread.serialize_field("id", &format!("{:?}", self.id))?;
+            thread.end()
+        }
+    }
+
+    impl Serialize for PanicLocation {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut location = serializer.serialize_struct("PanicLocation", 3)?;
+            location.serialize_field("file", &self.file)?;
+            location.serialize_field("line", &self.line)?;
+            location.serialize_field("col", &self.col)?;
+            location.end()
+        }
+    }
+
+    impl Serialize for Fingerprint {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+
+    /// The frames of a backtrace, given its [`Display`](std::fmt::Display) output.
+    struct Frames<'a>(&'a str);
+
+    impl Serialize for Frames<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let frames = parse_frames(self.0);
+            let mut seq = serializer.serialize_seq(Some(frames.len()))?;
+            for (symbol, location) in frames {
+                seq.serialize_element(&Frame { symbol, location })?;
+            }
+            seq.end()
+        }
+    }
+
+    /// A single frame of a backtrace.
+    struct Frame<'a> {
+        symbol: &'a str,
+        location: Option<&'a str>,
+    }
+
+    impl Serialize for Frame<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut frame = serializer.serialize_struct("Frame", 2)?;
+            frame.serialize_field("symbol", self.symbol)?;
+            frame.serialize_field("location", &self.location)?;
+            frame.end()
+        }
+    }
+
+    /// Key-value pairs, serialized as a map.
+    struct Pairs<'a>(&'a [(String, String)]);
+
+    impl Serialize for Pairs<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut map = serializer.serialize_map(Some(self.0.len()))?;
+            for (key, value) in self.0 {
+                map.serialize_entry(key, value)?;
+            }
+            map.end()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{backtrace::Backtrace, sync::Arc, time::Duration};
+
+    use super::*;
+
+    use crate::{Catcher, MemoryReporter, PanicLocation, ThreadInfo};
+
+    /// Helper function to create a report with fixed contents.
+    fn make_report() -> PanicReport {
+        PanicReport {
+            message: Some(String::from("oh \"no\"")),
+            payload_type: Some("String"),
+            context: vec![(String::from("user"), String::from("42"))],
+            tags: vec![(String::from("service"), String::from("billing"))],
+            location: Some(PanicLocation {
+                file: String::from("src/main.rs"),
+                line: 3,
+                col: 5,
+            }),
+            backtrace: Arc::new(Backtrace::disabled()),
+            thread: ThreadInfo {
+                name: Some(String::from("main")),
+                id: std::thread::current().id(),
+            },
+            timestamp: SystemTime::UNIX_EPOCH + Duration::from_millis(1500),
+        }
+    }
+
+    /// This test ensures reports are serialized in the documented schema.
+    #[test]
+    fn writes_schema() {
+        let report = make_report();
+
+        assert_eq!(
+            report.to_json_line(),
+            format!(
+                concat!(
+                    r#"{{"schema_version":1,"timestamp_ms":1500,"#,
+                    r#""thread":{{"name":"main","id":"{:?}"}},"#,
+                    r#""payload":"oh \"no\"","payload_type":"String","#,
+                    r#""location":{{"file":"src/main.rs","line":3,"col":5}},"backtrace":null,"#,
+                    r#""context":{{"user":"42"}},"tags":{{"service":"billing"}},"#,
+                    r#""fingerprint":"{}"}}"#,
+                    "\n"
+                ),
+                report.thread.id,
+                report.fingerprint()
+            )
+        );
+    }
+
+    /// This test ensures backtrace frames are written with their symbols and locations.
+    #[test]
+    fn writes_frames() {
+        let mut out = String::new();
+        write_frames(
+            &mut out,
+            "   0: app::inner\n             at ./src/main.rs:1:48\n   1: __rust_try\n",
+        );
+
+        assert_eq!(
+            out,
+            r#"[{"symbol":"app::inner","location":"./src/main.rs:1:48"},{"symbol":"__rust_try","location":null}]"#
+        );
+    }
+
+    /// This test ensures reports of caught panics include the catcher's tags and the payload's
+    /// context.
+    #[test]
+    fn includes_tags_and_context() {
+        let reporter = Arc::new(MemoryReporter::new());
+        let catcher = Catcher::new()
+            .tag("service", "billing")
+            .tag("region", "eu")
+            .reporter(Arc::clone(&reporter));
+
+        catcher
+            .catch(|| crate::panic_with!(user = 42; "declined"))
+            .unwrap_err();
+
+        let report = &reporter.reports()[0];
+        assert_eq!(report.payload_type, Some("chillpill::StructuredPayload"));
+        assert_eq!(report.context, [(String::from("user"), String::from("42"))]);
+        assert!(
+            report
+                .to_json_line()
+                .contains(r#""context":{"user":"42"},"tags":{"service":"billing","region":"eu"}"#)
+        );
+    }
+
+    /// This test ensures the `serde` serialization matches the built-in serialization.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_matches_built_in() {
+        let report = make_report();
+
+        assert_eq!(
+            serde_json::to_string(&report).unwrap() + "\n",
+            report.to_json_line()
+        );
+    }
+}
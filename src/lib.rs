@@ -4,21 +4,84 @@
 //! [`std::panic::catch_unwind`]: std::panic::catch_unwind
 //! [`chillpill::catch`]: crate::catch
 #![doc = include_str!("../README.md")]
-#![expect(
-    clippy::needless_doctest_main,
-    reason = "README.md contains example usage with a `fn main()` that also runs as a doctest"
-)]
 
+pub mod panic;
+
+mod assertion;
+mod cancellation_token;
+mod catch_group;
+mod catcher;
+mod circuit_breaker;
+mod crash_report;
+mod fatal;
+mod fingerprint;
+mod flight_recorder;
+mod json;
+mod json_lines;
 mod panic_data;
+mod panic_grouper;
 mod panic_hook;
+mod panic_kind;
+mod panic_report;
+mod payload_formatter;
+mod prometheus;
+mod reporter;
+mod retry;
+mod run_main;
+mod sentry;
+mod stats;
+mod structured_payload;
+mod subscription;
+mod supervisor;
 mod thread_local_catch_stack;
+#[cfg(feature = "tracing")]
+mod tracing_integration;
+mod typed_catch;
+mod uncaught;
+#[cfg(unix)]
+mod unix_socket_reporter;
 
 use std::panic::UnwindSafe;
 
-pub use panic_data::{PanicData, PanicLocation};
+pub use assertion::{AssertionFailure, AssertionOperator};
+pub use cancellation_token::CancellationToken;
+pub use catch_group::{CatchGroup, GroupPanics};
+pub use catcher::Catcher;
+pub use circuit_breaker::{CircuitBreaker, CircuitBreakerError, CircuitState};
+pub use crash_report::{CrashReportFormat, CrashReporter, take_crash_reporter};
+pub use fatal::{FatalPanic, set_abort_on_fatal_panic};
+pub use fingerprint::Fingerprint;
+pub use flight_recorder::{recent_panics, set_recent_panics_capacity};
+pub use json_lines::JSON_LINES_SCHEMA_VERSION;
+pub use panic_data::{PanicData, PanicLocation, TypedPanic};
+pub use panic_grouper::{PanicGroup, PanicGrouper};
+pub use panic_kind::{PanicKind, UnwrappedValue};
+pub use panic_report::{PanicReport, PanicSnapshot, ThreadInfo};
+pub use payload_formatter::{
+    register_debug_payload, register_display_payload, register_error_payload,
+    register_payload_formatter,
+};
+pub use prometheus::PrometheusRenderer;
+pub use reporter::{FanOut, FileReporter, MemoryReporter, PanicReporter, StderrReporter};
+pub use retry::{Backoff, RetryPolicy, retry};
+pub use run_main::{MainConfig, main};
+pub use stats::{PanicCounts, PanicStats, reset_stats, stats};
+pub use structured_payload::StructuredPayload;
+pub use subscription::{PanicEvent, subscribe, subscribe_with_capacity};
+pub use supervisor::{RestartStrategy, Supervisor, SupervisorExit, WorkerPanic};
+#[cfg(feature = "tracing")]
+pub use tracing_integration::PanicContextLayer;
+pub use typed_catch::catch_typed;
+pub use uncaught::{
+    UncaughtAction, flush_uncaught_reporter, set_uncaught_handler, set_uncaught_reporter,
+    take_uncaught_handler, take_uncaught_reporter,
+};
+#[cfg(unix)]
+pub use unix_socket_reporter::{DropPolicy, UnixSocketReporter, UnixSocketReporterConfig};
 
-use crate::thread_local_catch_stack::{
-    CaptureBacktrace, CatchStackFrame, THREAD_LOCAL_CATCH_STACK,
+use crate::{
+    panic_data::PanicContext,
+    thread_local_catch_stack::{CaptureBacktrace, CatchStackFrame, THREAD_LOCAL_CATCH_STACK},
 };
 
 /// A specialized [`Result`] type for chillpill.
@@ -59,10 +122,14 @@ pub type Result<T> = std::result::Result<T, PanicData>;
 /// after the first call to this function. Replacing the panic hook is uncommon, so usually this is
 /// the easy (and correct) solution.
 ///
-/// If other code does need to replace the panic hook, it must ensure that their panic hook invokes
-/// ours at some point during its execution for unwinding panics. That is sufficient to ensure
-/// chillpill can still capture panic information, although chillpill cannot prevent the new "outer"
-/// panic hook from printing to stderr if it attempts to.
+/// If other code does need to replace the panic hook, the easiest option is to use
+/// [`chillpill::panic::set_hook`] instead of [`std::panic::set_hook`]. It installs the new hook
+/// *beneath* the chillpill panic hook, where it is invoked for every panic not caught by chillpill.
+///
+/// Otherwise, the code replacing the panic hook must ensure that its panic hook invokes ours at some
+/// point during its execution for unwinding panics. That is sufficient to ensure chillpill can still
+/// capture panic information, although chillpill cannot prevent the new "outer" panic hook from
+/// printing to stderr if it attempts to.
 ///
 /// # No Hook Panics
 ///
@@ -93,6 +160,7 @@ pub type Result<T> = std::result::Result<T, PanicData>;
 ///
 /// [`catch_force_backtrace`]: catch_force_backtrace
 /// [`catch_never_backtrace`]: catch_never_backtrace
+/// [`chillpill::panic::set_hook`]: crate::panic::set_hook
 /// [`Backtrace::disabled()`]: std::backtrace::Backtrace::disabled
 pub fn catch<F: FnOnce() -> R + UnwindSafe, R>(f: F) -> Result<R> {
     catch_inner(f, CaptureBacktrace::Default)
@@ -132,6 +200,10 @@ fn catch_inner<F: FnOnce() -> R + UnwindSafe, R>(
     f: F,
     capture_backtrace: CaptureBacktrace,
 ) -> Result<R> {
+    catch_in_frame(f, CatchStackFrame::new(capture_backtrace))
+}
+
+fn catch_in_frame<F: FnOnce() -> R + UnwindSafe, R>(f: F, frame: CatchStackFrame) -> Result<R> {
     // Ensure the chillpill panic hook is installed
     if let Err(()) = panic_hook::install_if_not_installed() {
         panic!("the first call to `chillpill::catch` must not be made from a panicking thread");
@@ -140,7 +212,7 @@ fn catch_inner<F: FnOnce() -> R + UnwindSafe, R>(
     // Push a new frame corresponding to this call to `catch_inner`. See the documentation on
     // `THREAD_LOCAL_CATCH_STACK` for details.
     THREAD_LOCAL_CATCH_STACK.with_borrow_mut(|stack| {
-        stack.push(CatchStackFrame::new(capture_backtrace));
+        stack.push(frame);
     });
 
     // Call the provided closure, using `std::panic::catch_unwind` to catch the panic payload and
@@ -153,15 +225,31 @@ fn catch_inner<F: FnOnce() -> R + UnwindSafe, R>(
         .with_borrow_mut(Vec::pop)
         .expect("catch stack should not be empty, since we just pushed a frame - this is a bug in chillpill");
 
-    // If the closure panicked, combine the payload, location, and backtrace into a `PanicData`
+    // If the panic was rejected by this frame's filter (or is fatal), let it continue unwinding. The
+    // panic hook already recorded its location and backtrace in the frame of the catch that
+    // accepted it (if any), and `resume_unwind` does not invoke the panic hook again.
+    //
+    // Fatal panics are checked here too, since panics resumed with `resume_unwind` never reach the
+    // panic hook to mark the frame.
+    let catch_unwind_result = match catch_unwind_result {
+        Err(payload) if frame.rejected || fatal::is_fatal(&*payload) => {
+            std::panic::resume_unwind(payload)
+        }
+        result => result,
+    };
+
+    // If the closure panicked, combine the payload, location, backtrace, and context into a
+    // `PanicData`
     catch_unwind_result.map_err(|payload| {
         let location = frame.location;
         let backtrace = frame.backtrace;
+        let context = PanicContext::from(frame.context);
 
         PanicData {
             payload,
             location,
             backtrace,
+            context,
         }
     })
 }
@@ -0,0 +1,96 @@
+//! Drop-in replacements for the panic hook functions in [`std::panic`](mod@std::panic).
+//!
+//! Replacing the global panic hook with [`std::panic::set_hook`] after the chillpill panic hook is
+//! installed removes the chillpill panic hook, breaking [`chillpill::catch`] (see its documentation
+//! for details). The functions in this module have the same signatures as their `std::panic`
+//! counterparts, but operate on the hook *beneath* the chillpill panic hook instead. That hook is
+//! invoked for every panic that is not caught by `chillpill::catch`, exactly as if it were the
+//! global panic hook.
+//!
+//! Code that replaces the panic hook can switch to these functions by changing a single import:
+//!
+//! ```
+//! use chillpill::panic::{set_hook, take_hook};
+//!
+//! set_hook(Box::new(|info| {
+//!     eprintln!("custom panic hook: {info}");
+//! }));
+//!
+//! // This panic is caught by chillpill, so the custom hook is not invoked
+//! chillpill::catch(|| panic!("caught")).unwrap_err();
+//!
+//! // Restore the default panic hook
+//! let _ = take_hook();
+//! ```
+//!
+//! [`chillpill::catch`]: crate::catch
+
+use std::{panic::PanicHookInfo, sync::Arc};
+
+use crate::panic_hook::{self, PanicHook};
+
+/// Registers a custom panic hook beneath the chillpill panic hook, replacing the previously
+/// registered hook.
+///
+/// This is the chillpill equivalent of [`std::panic::set_hook`]. The hook is invoked for every
+/// panic that is not caught by [`chillpill::catch`], and is never invoked for panics that are.
+///
+/// # Panics
+///
+/// Panics if the chillpill panic hook is not yet installed and this thread is currently unwinding
+/// from a panic.
+///
+/// [`chillpill::catch`]: crate::catch
+pub fn set_hook(hook: Box<dyn Fn(&PanicHookInfo<'_>) + Sync + Send + 'static>) {
+    install_hook_or_panic();
+
+    drop(panic_hook::set_previous_hook(Some(Arc::from(hook))));
+}
+
+/// Unregisters the current panic hook beneath the chillpill panic hook and returns it, registering
+/// the default hook in its place.
+///
+/// This is the chillpill equivalent of [`std::panic::take_hook`]. The chillpill panic hook itself is
+/// left in place.
+///
+/// # Panics
+///
+/// Panics if the chillpill panic hook is not yet installed and this thread is currently unwinding
+/// from a panic.
+pub fn take_hook() -> Box<dyn Fn(&PanicHookInfo<'_>) + Sync + Send + 'static> {
+    install_hook_or_panic();
+
+    match panic_hook::set_previous_hook(None) {
+        Some(hook) => Box::new(move |info| hook(info)),
+        None => Box::new(panic_hook::call_default_hook),
+    }
+}
+
+/// Atomically replaces the panic hook beneath the chillpill panic hook with a new one that has
+/// access to the old one.
+///
+/// This is the chillpill equivalent of the (currently unstable) `std::panic::update_hook`.
+///
+/// # Panics
+///
+/// Panics if the chillpill panic hook is not yet installed and this thread is currently unwinding
+/// from a panic.
+pub fn update_hook<F>(hook_fn: F)
+where
+    F: Fn(&(dyn Fn(&PanicHookInfo<'_>) + Send + Sync + 'static), &PanicHookInfo<'_>)
+        + Sync
+        + Send
+        + 'static,
+{
+    install_hook_or_panic();
+
+    panic_hook::update_previous_hook(|previous_hook: PanicHook| {
+        Arc::new(move |info| hook_fn(&*previous_hook, info))
+    });
+}
+
+fn install_hook_or_panic() {
+    if let Err(()) = panic_hook::install_if_not_installed() {
+        panic!("cannot modify the panic hook from a panicking thread");
+    }
+}
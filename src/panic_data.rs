@@ -1,4 +1,10 @@
-use std::{any::Any, backtrace::Backtrace, borrow::Cow, fmt::Display};
+use std::{any::Any, backtrace::Backtrace, borrow::Cow, fmt::Display, ops::Deref, panic::Location};
+
+use crate::{
+    AssertionFailure, FatalPanic, Fingerprint, PanicKind, StructuredPayload, assertion,
+    fingerprint, panic_kind,
+    payload_formatter::{self, payload_to_string},
+};
 
 /// The payload and source code location of a panic.
 pub struct PanicData {
@@ -24,6 +30,9 @@ pub struct PanicData {
     /// [`chillpill::catch_never_backtrace`]: crate::catch_never_backtrace
     /// [`chillpill::catch`]: crate::catch
     pub backtrace: Backtrace,
+
+    /// Key-value context about where the panic happened (see [`PanicData::context`]).
+    pub(crate) context: PanicContext,
 }
 
 impl std::fmt::Debug for PanicData {
@@ -38,38 +47,121 @@ impl std::fmt::Debug for PanicData {
             )
             .field("location", &self.location)
             .field("backtrace", &self.backtrace)
+            .field("context", &self.context)
             .finish()
     }
 }
 
 impl PanicData {
-    /// Attempts to convert the panic payload to a string (either [`&str`](str) or [`String`]),
-    /// returning [`None`] if the payload was neither `&str` nor `String`.
-    pub fn payload_as_string(&self) -> Option<&str> {
-        // Try downcasting to a &str
-        if let Some(s) = self.payload.downcast_ref::<&str>() {
-            return Some(s);
-        }
+    /// Attempts to convert the panic payload to a string (either [`&str`](str) or [`String`], or
+    /// the message of a [`FatalPanic`] or [`StructuredPayload`]), falling back to the payload
+    /// formatter registered for the payload's type (see [`chillpill::register_payload_formatter`]).
+    /// Returns [`None`] if none of those apply.
+    ///
+    /// [`FatalPanic`]: crate::FatalPanic
+    /// [`StructuredPayload`]: crate::StructuredPayload
+    /// [`chillpill::register_payload_formatter`]: crate::register_payload_formatter
+    pub fn payload_as_string(&self) -> Option<Cow<'_, str>> {
+        payload_to_string(&*self.payload)
+    }
 
-        // Downcasting to a &str failed, try downcasting to a String
-        if let Some(s) = self.payload.downcast_ref::<String>() {
-            return Some(s);
-        }
+    /// Returns the name of the type of the panic payload, if it is one chillpill knows how to
+    /// convert to a string (see [`PanicData::payload_as_string`]). Returns [`None`] otherwise.
+    ///
+    /// The standard library's string payloads are named `"&str"` and `"String"`. Registered payload
+    /// types are named by [`std::any::type_name`], so their names are not guaranteed to be stable.
+    pub fn payload_type_name(&self) -> Option<&'static str> {
+        payload_formatter::payload_type_name(&*self.payload)
+    }
+
+    /// Classifies the panic by its cause, for common panics raised by the standard library (like
+    /// unwrapping a `None`, or indexing out of bounds).
+    ///
+    /// The classification is based on the payload's message, so only `&str` and `String` payloads
+    /// are classified - any other payload is [`PanicKind::Custom`].
+    pub fn kind(&self) -> PanicKind {
+        panic_kind::classify(&*self.payload)
+    }
+
+    /// Parses the parts of a failed [`assert_eq!`] or [`assert_ne!`] assertion (the left and right
+    /// values, the comparison, and the custom message) out of the panic message, or returns
+    /// [`None`] if the panic was not such an assertion failure.
+    pub fn assertion(&self) -> Option<AssertionFailure> {
+        panic_kind::std_message(&*self.payload).and_then(assertion::parse_assertion_failure)
+    }
+
+    /// Computes a stable fingerprint of the panic from its location and normalized message (with
+    /// numbers, addresses, and quoted values stripped), to identify panics caused by the same bug.
+    ///
+    /// See [`Fingerprint`] and [`PanicData::fingerprint_with_frames`].
+    ///
+    /// [`Fingerprint`]: crate::Fingerprint
+    pub fn fingerprint(&self) -> Fingerprint {
+        self.fingerprint_with_frames(0)
+    }
+
+    /// Like [`PanicData::fingerprint`], but also including (at most) the top `frames` frames of the
+    /// backtrace that are in the user's crates, below the panic machinery.
+    ///
+    /// This distinguishes panics at the same location reached through different callers (like a
+    /// shared helper that panics). A frame is considered in-crate if the standard library prints
+    /// its source path as a relative path, which it does for paths within the current directory.
+    /// If the backtrace was not captured, this is the same as [`PanicData::fingerprint`].
+    ///
+    /// [`Fingerprint`]: crate::Fingerprint
+    pub fn fingerprint_with_frames(&self, frames: usize) -> Fingerprint {
+        Fingerprint::compute(
+            self.location.as_ref(),
+            self.payload_as_string().as_deref(),
+            &fingerprint::in_crate_frames(&self.backtrace, frames),
+        )
+    }
 
-        // Downcasting to a String failed, give up and return None
-        None
+    /// Returns the error code of a [`StructuredPayload`], or [`None`] if the payload is not
+    /// structured or has no code.
+    ///
+    /// [`StructuredPayload`]: crate::StructuredPayload
+    pub fn code(&self) -> Option<&str> {
+        self.payload
+            .downcast_ref::<StructuredPayload>()
+            .and_then(StructuredPayload::code)
     }
 
-    /// Attempts to convert the panic payload to a string (either [`&str`](str) or [`String`]).
+    /// Returns key-value context about where the panic happened.
+    ///
+    /// With the `tracing` feature enabled, this is the name of the `tracing` span that was current
+    /// on the panicking thread (under the `"span"` key), followed by its fields if the subscriber
+    /// records them. Otherwise, it is empty.
+    #[cfg_attr(
+        feature = "tracing",
+        doc = "",
+        doc = "Span fields are recorded by a [`PanicContextLayer`](crate::PanicContextLayer)."
+    )]
+    pub fn context(&self) -> &[(String, String)] {
+        &self.context
+    }
+
+    /// Returns the key-value fields of a [`StructuredPayload`], or an empty slice if the payload is
+    /// not structured.
+    ///
+    /// [`StructuredPayload`]: crate::StructuredPayload
+    pub fn fields(&self) -> &[(String, String)] {
+        self.payload
+            .downcast_ref::<StructuredPayload>()
+            .map_or(&[], StructuredPayload::fields)
+    }
+
+    /// Attempts to convert the panic payload to a string, like [`PanicData::payload_as_string`].
     ///
     /// # Errors
     ///
-    /// Returns `self` back if the panic payload was neither a `&str` nor a `String`.
+    /// Returns `self` back if the panic payload could not be converted to a string.
     pub fn payload_into_string(self) -> Result<Cow<'static, str>, Self> {
         let Self {
             payload,
             location,
             backtrace,
+            context,
         } = self;
 
         // Try downcasting to a &str
@@ -84,13 +176,109 @@ impl PanicData {
             Err(any) => any,
         };
 
-        // Downcasting to a String failed, give up and return a re-created Self
+        // Downcasting to a String failed, try the other payloads chillpill can describe
+        if let Some(s) = payload_to_string(&*payload).map(Cow::into_owned) {
+            return Ok(Cow::Owned(s));
+        }
+
+        // Converting the payload failed, give up and return a re-created Self
         Err(Self {
             payload,
             location,
             backtrace,
+            context,
         })
     }
+
+    /// Attempts to downcast the panic payload to a concrete type.
+    ///
+    /// # Errors
+    ///
+    /// Returns `self` back if the panic payload was not of type `E`.
+    pub fn downcast<E: Any + Send>(self) -> Result<TypedPanic<E>, Self> {
+        let Self {
+            payload,
+            location,
+            backtrace,
+            context,
+        } = self;
+
+        match payload.downcast::<E>() {
+            Ok(payload) => Ok(TypedPanic {
+                payload: *payload,
+                location,
+                backtrace,
+                context,
+            }),
+            Err(payload) => Err(Self {
+                payload,
+                location,
+                backtrace,
+                context,
+            }),
+        }
+    }
+}
+
+/// Like [`PanicData`], but with a payload of a known type.
+///
+/// See [`chillpill::catch_typed`] and [`PanicData::downcast`].
+///
+/// [`chillpill::catch_typed`]: crate::catch_typed
+#[derive(Debug)]
+pub struct TypedPanic<E> {
+    /// The payload associated with the panic.
+    pub payload: E,
+
+    /// The source code location of the panic, or [`None`] if no source location was available.
+    ///
+    /// See [`PanicData::location`].
+    pub location: Option<PanicLocation>,
+
+    /// A backtrace captured at the time of the panic (specifically, within the panic hook).
+    ///
+    /// See [`PanicData::backtrace`].
+    pub backtrace: Backtrace,
+
+    /// Key-value context about where the panic happened (see [`PanicData::context`]).
+    pub(crate) context: PanicContext,
+}
+
+impl<E> TypedPanic<E> {
+    /// Returns key-value context about where the panic happened.
+    ///
+    /// See [`PanicData::context`].
+    pub fn context(&self) -> &[(String, String)] {
+        &self.context
+    }
+}
+
+/// Attempts to convert a panic payload to a string (either [`&str`](str) or [`String`], or the
+/// message of a [`FatalPanic`] or [`StructuredPayload`]), returning [`None`] if the payload was
+/// none of those.
+pub(crate) fn payload_as_str(payload: &(dyn Any + Send)) -> Option<&str> {
+    // Try downcasting to a &str
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        return Some(s);
+    }
+
+    // Downcasting to a &str failed, try downcasting to a String
+    if let Some(s) = payload.downcast_ref::<String>() {
+        return Some(s);
+    }
+
+    // Downcasting to a String failed, try downcasting to a FatalPanic
+    if let Some(fatal_panic) = payload.downcast_ref::<FatalPanic>() {
+        return Some(fatal_panic.message());
+    }
+
+    // Downcasting to a FatalPanic failed, try downcasting to a StructuredPayload
+    if let Some(structured_payload) = payload.downcast_ref::<StructuredPayload>() {
+        return Some(structured_payload.message());
+    }
+
+    // Downcasting to a StructuredPayload failed, give up and return None
+    None
 }
 
 /// The source code location of a panic.
@@ -109,12 +297,55 @@ pub struct PanicLocation {
     pub col: u32,
 }
 
+impl From<&Location<'_>> for PanicLocation {
+    fn from(location: &Location<'_>) -> Self {
+        Self {
+            file: location.file().to_string(),
+            line: location.line(),
+            col: location.column(),
+        }
+    }
+}
+
 impl Display for PanicLocation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}:{}:{}", self.file, self.line, self.col)
     }
 }
 
+/// Key-value context about where a panic happened (see [`PanicData::context`]), which dereferences
+/// to a slice of key-value pairs.
+///
+/// An empty context does not allocate, and a non-empty one is stored behind a single pointer, to
+/// keep [`PanicData`] small.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub(crate) struct PanicContext(Option<Box<ContextEntries>>);
+
+/// The entries of a non-empty [`PanicContext`], in a struct so that they can be boxed behind a thin
+/// pointer.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct ContextEntries(Vec<(String, String)>);
+
+impl Deref for PanicContext {
+    type Target = [(String, String)];
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref().map_or(&[], |entries| &entries.0)
+    }
+}
+
+impl From<Vec<(String, String)>> for PanicContext {
+    fn from(entries: Vec<(String, String)>) -> Self {
+        Self((!entries.is_empty()).then(|| Box::new(ContextEntries(entries))))
+    }
+}
+
+impl std::fmt::Debug for PanicContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -129,6 +360,7 @@ mod tests {
             payload: Box::new(payload),
             location,
             backtrace,
+            context: PanicContext::default(),
         }
     }
 
@@ -150,12 +382,15 @@ mod tests {
             location: Option<PanicLocation>,
             #[expect(dead_code, reason = "we actually care about the derived Debug")]
             backtrace: Backtrace,
+            #[expect(dead_code, reason = "we actually care about the derived Debug")]
+            context: PanicContext,
         }
 
         PanicData {
             payload: Box::new(expected_payload),
             location,
             backtrace,
+            context: PanicContext::default(),
         }
     }
 
@@ -251,7 +486,10 @@ mod tests {
     fn payload_as_string_str() {
         let panic_data = make_panic_data("static str", None, Backtrace::disabled());
 
-        assert_eq!(panic_data.payload_as_string(), Some("static str"));
+        assert_eq!(
+            panic_data.payload_as_string().as_deref(),
+            Some("static str")
+        );
     }
 
     /// This test ensures [`PanicData::payload_as_string`] correctly extracts a [`String`] payload.
@@ -259,7 +497,10 @@ mod tests {
     fn payload_as_string_string() {
         let panic_data = make_panic_data(String::from("owned string"), None, Backtrace::disabled());
 
-        assert_eq!(panic_data.payload_as_string(), Some("owned string"));
+        assert_eq!(
+            panic_data.payload_as_string().as_deref(),
+            Some("owned string")
+        );
     }
 
     /// This test ensures [`PanicData::payload_as_string`] correctly returns [`None`] for a payload
@@ -291,6 +532,17 @@ mod tests {
         assert!(matches!(result, Ok(Cow::Owned(ref s)) if s == "owned string"));
     }
 
+    /// This test ensures [`PanicData::payload_into_string`] extracts the message of a structured
+    /// payload, like [`PanicData::payload_as_string`].
+    #[test]
+    fn payload_into_string_structured() {
+        let payload = StructuredPayload::new("structured").with_code("E1");
+        let panic_data = make_panic_data(payload, None, Backtrace::disabled());
+        let result = panic_data.payload_into_string();
+
+        assert!(matches!(result, Ok(Cow::Owned(ref s)) if s == "structured"));
+    }
+
     /// This test ensures [`PanicData::payload_into_string`] correctly returns [`None`] for a
     /// payload that is neither a [`&str`](str) nor a [`String`].
     #[test]
@@ -300,4 +552,36 @@ mod tests {
 
         assert_eq!(*result.payload.downcast::<u32>().unwrap(), 1234u32);
     }
+
+    /// This test ensures [`PanicData::downcast`] correctly extracts a payload of the right type.
+    #[test]
+    fn downcast_matching_type() {
+        let panic_data = make_panic_data(1234u32, None, Backtrace::disabled());
+        let typed_panic = panic_data.downcast::<u32>().unwrap();
+
+        assert_eq!(typed_panic.payload, 1234u32);
+    }
+
+    /// This test ensures [`PanicData::downcast`] returns `self` back for a payload of another type.
+    #[test]
+    fn downcast_other_type() {
+        let panic_data = make_panic_data(1234u32, None, Backtrace::disabled());
+        let panic_data = panic_data.downcast::<u64>().unwrap_err();
+
+        assert_eq!(*panic_data.payload.downcast::<u32>().unwrap(), 1234u32);
+    }
+
+    /// This test ensures a [`PanicContext`] only allocates when non-empty, and dereferences to its
+    /// entries.
+    #[test]
+    fn panic_context_entries() {
+        let empty = PanicContext::from(Vec::new());
+        assert!(empty.0.is_none());
+        assert!(empty.is_empty());
+        assert_eq!(format!("{empty:?}"), "[]");
+
+        let context = PanicContext::from(vec![(String::from("span"), String::from("request"))]);
+        assert_eq!(*context, [(String::from("span"), String::from("request"))]);
+        assert_eq!(format!("{context:?}"), r#"[("span", "request")]"#);
+    }
 }
@@ -0,0 +1,136 @@
+use std::{collections::HashMap, time::SystemTime};
+
+use crate::{Fingerprint, PanicData, PanicSnapshot};
+
+/// A group of panics with the same [`Fingerprint`], aggregated by a [`PanicGrouper`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanicGroup {
+    /// The fingerprint shared by every panic in the group.
+    pub fingerprint: Fingerprint,
+
+    /// The number of panics recorded in the group.
+    pub count: u64,
+
+    /// The time the first panic in the group was recorded.
+    pub first_seen: SystemTime,
+
+    /// The time the most recent panic in the group was recorded.
+    pub last_seen: SystemTime,
+
+    /// A snapshot of the first panic recorded in the group.
+    pub sample: PanicSnapshot,
+}
+
+/// A collection of caught panics, grouped by [`Fingerprint`] to deduplicate panics caused by the
+/// same bug.
+///
+/// Each group keeps a count, the times it was first and last seen, and one sample panic. Only the
+/// sample's message and location are kept, so memory use grows with the number of distinct bugs,
+/// not the number of panics.
+///
+/// # Example
+///
+/// ```
+/// let mut grouper = chillpill::PanicGrouper::new();
+///
+/// for id in 0..3 {
+///     let panic_data = chillpill::catch(|| panic!("no such user: {id}")).unwrap_err();
+///     grouper.record(&panic_data);
+/// }
+///
+/// assert_eq!(grouper.len(), 1);
+/// let group = grouper.groups().next().unwrap();
+/// assert_eq!(group.count, 3);
+/// assert_eq!(group.sample.message.as_deref(), Some("no such user: 0"));
+/// ```
+#[derive(Debug, Default)]
+pub struct PanicGrouper {
+    frames: usize,
+    groups: HashMap<Fingerprint, PanicGroup>,
+}
+
+impl PanicGrouper {
+    /// Creates an empty grouper, which fingerprints panics with [`PanicData::fingerprint`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fingerprints panics with [`PanicData::fingerprint_with_frames`], including (at most) the top
+    /// `frames` in-crate backtrace frames.
+    #[must_use]
+    pub fn frames(mut self, frames: usize) -> Self {
+        self.frames = frames;
+        self
+    }
+
+    /// Records a panic in its group (creating the group if needed), returning its fingerprint.
+    pub fn record(&mut self, panic_data: &PanicData) -> Fingerprint {
+        let fingerprint = panic_data.fingerprint_with_frames(self.frames);
+        let now = SystemTime::now();
+
+        self.groups
+            .entry(fingerprint)
+            .and_modify(|group| {
+                group.count += 1;
+                group.last_seen = now;
+            })
+            .or_insert_with(|| PanicGroup {
+                fingerprint,
+                count: 1,
+                first_seen: now,
+                last_seen: now,
+                sample: PanicSnapshot::from_panic_data(panic_data),
+            });
+
+        fingerprint
+    }
+
+    /// Returns the group with the given fingerprint, if any panics with it were recorded.
+    pub fn group(&self, fingerprint: Fingerprint) -> Option<&PanicGroup> {
+        self.groups.get(&fingerprint)
+    }
+
+    /// Returns every group, in no particular order.
+    pub fn groups(&self) -> impl Iterator<Item = &PanicGroup> {
+        self.groups.values()
+    }
+
+    /// Returns the number of groups.
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Returns whether no panics were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::catch;
+
+    /// Helper function that panics with a message including `n`, always at the same location.
+    fn panic_with(n: u32) {
+        panic!("request {n} failed");
+    }
+
+    /// This test ensures panics are grouped by fingerprint, keeping the first one as the sample.
+    #[test]
+    fn groups_by_fingerprint() {
+        let mut grouper = PanicGrouper::new();
+
+        let first = grouper.record(&catch(|| panic_with(1)).unwrap_err());
+        let other = grouper.record(&catch(|| panic!("something else")).unwrap_err());
+        assert_eq!(grouper.record(&catch(|| panic_with(2)).unwrap_err()), first);
+
+        assert_eq!(grouper.len(), 2);
+        let group = grouper.group(first).unwrap();
+        assert_eq!(group.count, 2);
+        assert!(group.first_seen <= group.last_seen);
+        assert_eq!(group.sample.message.as_deref(), Some("request 1 failed"));
+        assert_eq!(grouper.group(other).unwrap().count, 1);
+    }
+}
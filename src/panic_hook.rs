@@ -1,20 +1,50 @@
-use std::{backtrace::Backtrace, panic::PanicHookInfo, sync::Once};
+use std::{
+    cell::OnceCell,
+    panic::PanicHookInfo,
+    sync::{Arc, Once, OnceLock, PoisonError, RwLock},
+};
 
+#[cfg(feature = "tracing")]
+use crate::tracing_integration;
 use crate::{
+    PanicReport, crash_report, fatal, flight_recorder,
     panic_data::PanicLocation,
+    stats, subscription,
     thread_local_catch_stack::{CaptureBacktrace, THREAD_LOCAL_CATCH_STACK},
+    uncaught,
 };
 
-type PanicHook = Box<dyn Fn(&PanicHookInfo<'_>) + Send + Sync>;
+/// A panic hook, shared so that it can be invoked without holding the lock it is stored behind.
+pub type PanicHook = Arc<dyn Fn(&PanicHookInfo<'_>) + Send + Sync>;
+
+/// The panic hook that lives "beneath" the chillpill panic hook.
+///
+/// When the chillpill panic hook is installed, the previously installed global panic hook is moved
+/// here. The chillpill panic hook delegates to this hook for every panic that is not caught by a
+/// `chillpill::catch` call. It can be replaced at any time (see the [`crate::panic`] module) without
+/// disturbing the chillpill panic hook itself.
+///
+/// A value of [`None`] means the standard library's default panic hook (see [`DEFAULT_HOOK`]).
+static PREVIOUS_HOOK: RwLock<Option<PanicHook>> = RwLock::new(None);
+
+/// The standard library's default panic hook, captured when the chillpill panic hook is installed.
+static DEFAULT_HOOK: OnceLock<PanicHook> = OnceLock::new();
 
 /// Installs the chillpill panic hook if it is not already installed.
 ///
 /// # Errors
 ///
-/// Returns an error without attempting to modify the panic hook if the current thread is panicking.
+/// Returns an error without attempting to modify the panic hook if it is not yet installed and the
+/// current thread is panicking.
 pub fn install_if_not_installed() -> Result<(), ()> {
     static CHILLPILL_HOOK_INSTALLED: Once = Once::new();
 
+    // Once installed, there is nothing left to do (even from a panicking thread, like from within a
+    // hook beneath the chillpill panic hook)
+    if CHILLPILL_HOOK_INSTALLED.is_completed() {
+        return Ok(());
+    }
+
     // If the current thread is panicking, we cannot install the panic hook.
     //
     // There's fun tricks you can do with spawning a new thread and installing the hook there, but
@@ -28,40 +58,184 @@ pub fn install_if_not_installed() -> Result<(), ()> {
     CHILLPILL_HOOK_INSTALLED.call_once(|| {
         // TODO(ijchen): use `std::panic::update_hook` once stable (#92649)
         let old_hook = std::panic::take_hook();
-        let new_hook = Box::new(make_chillpill_panic_hook(old_hook));
-        std::panic::set_hook(new_hook);
+
+        // `take_hook` leaves the default hook in place, so taking it a second time hands us the
+        // default hook itself
+        let default_hook = std::panic::take_hook();
+        let _ = DEFAULT_HOOK.set(Arc::from(default_hook));
+
+        set_previous_hook(Some(Arc::from(old_hook)));
+        std::panic::set_hook(Box::new(chillpill_panic_hook));
     });
 
     Ok(())
 }
 
-fn make_chillpill_panic_hook(
-    previous_hook: PanicHook,
-) -> impl Fn(&PanicHookInfo<'_>) + Send + Sync {
-    move |info| {
-        // Grab the top frame from `THREAD_LOCAL_CATCH_STACK` (or if it's empty, transparently
-        // delegate to the previous panic hook)
-        THREAD_LOCAL_CATCH_STACK.with_borrow_mut(|stack| {
-            match stack.last_mut() {
-                Some(top_frame) => {
-                    // Smuggle out the panic location and backtrace, storing them in
-                    // `THREAD_LOCAL_CATCH_STACK` to be extracted later.
-                    top_frame.location = info.location().map(|location| PanicLocation {
-                        file: location.file().to_string(),
-                        line: location.line(),
-                        col: location.column(),
-                    });
-                    top_frame.backtrace = match top_frame.capture_backtrace {
-                        CaptureBacktrace::Always => Backtrace::force_capture(),
-                        CaptureBacktrace::Default => Backtrace::capture(),
-                        CaptureBacktrace::Never => Backtrace::disabled(),
-                    };
-                }
-
-                // If `THREAD_LOCAL_CATCH_STACK` is empty, the panicking thread is not in a
-                // `chillpill::catch` call - transparently delegate to the previous panic hook.
-                None => previous_hook(info),
-            }
+/// Replaces the hook beneath the chillpill panic hook, returning the old one.
+///
+/// A value of [`None`] means the standard library's default panic hook.
+pub fn set_previous_hook(hook: Option<PanicHook>) -> Option<PanicHook> {
+    let mut previous_hook = PREVIOUS_HOOK
+        .write()
+        .unwrap_or_else(PoisonError::into_inner);
+
+    std::mem::replace(&mut *previous_hook, hook)
+}
+
+/// Atomically replaces the hook beneath the chillpill panic hook with one derived from it.
+pub fn update_previous_hook(update: impl FnOnce(PanicHook) -> PanicHook) {
+    let mut previous_hook = PREVIOUS_HOOK
+        .write()
+        .unwrap_or_else(PoisonError::into_inner);
+
+    let old_hook = previous_hook
+        .take()
+        .unwrap_or_else(|| Arc::new(call_default_hook));
+    *previous_hook = Some(update(old_hook));
+}
+
+/// Invokes the hook beneath the chillpill panic hook.
+pub fn call_previous_hook(info: &PanicHookInfo<'_>) {
+    // Call the hook without the lock held, so that it is free to replace itself
+    let previous_hook = PREVIOUS_HOOK
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+
+    match previous_hook {
+        Some(hook) => hook(info),
+        None => call_default_hook(info),
+    }
+}
+
+/// Invokes the standard library's default panic hook.
+pub fn call_default_hook(info: &PanicHookInfo<'_>) {
+    let default_hook = DEFAULT_HOOK
+        .get()
+        .expect("the default hook should be captured on install - this is a bug in chillpill");
+
+    default_hook(info);
+}
+
+fn chillpill_panic_hook(info: &PanicHookInfo<'_>) {
+    // Write a crash report first if this panic is about to abort the process
+    crash_report::observe_panic(info);
+    flight_recorder::record_panic(info);
+
+    // The backtrace of the panic, captured once on first use (by a subscriber, a reporter, the
+    // `tracing` event, or the uncaught panic handler) and shared between them
+    let backtrace = OnceCell::new();
+
+    // Fatal panics are never caught by chillpill
+    if fatal::is_fatal(info.payload()) {
+        stats::record_panic(info, false, None);
+        subscription::notify_subscribers(info, false, None, || {
+            Arc::clone(uncaught::backtrace(&backtrace))
+        });
+        #[cfg(feature = "tracing")]
+        tracing_integration::record_panic(info, false, || {
+            tracing_integration::captured_backtrace(uncaught::backtrace(&backtrace))
         });
+        fatal::handle_fatal_panic(info, &backtrace);
+        return;
+    }
+
+    // Find the frame of the `chillpill::catch` call that will catch this panic (or if there is
+    // none, hand the panic to the uncaught panic handler, which by default transparently delegates
+    // to the previous panic hook)
+    //
+    // The uncaught panic handler is called outside of `with_borrow_mut` so that the handler and
+    // previous hook are free to call `chillpill::catch` themselves.
+    let catching_frame = find_catching_frame(info);
+    let (capture_backtrace, caught_after, catch_label, reporter) =
+        catching_frame.map_or((CaptureBacktrace::Default, None, None, None), |index| {
+            THREAD_LOCAL_CATCH_STACK.with_borrow(|stack| {
+                let frame = &stack[index];
+                let reporter = frame
+                    .reporter
+                    .clone()
+                    .map(|reporter| (reporter, frame.tags.clone()));
+                let caught_after = frame.started.map(|started| started.elapsed());
+                (
+                    frame.capture_backtrace,
+                    caught_after,
+                    frame.label.clone(),
+                    reporter,
+                )
+            })
+        });
+    let shared_backtrace = || backtrace.get_or_init(|| Arc::new(capture_backtrace.capture()));
+    stats::record_panic(info, catching_frame.is_some(), caught_after);
+    subscription::notify_subscribers(
+        info,
+        catching_frame.is_some(),
+        catch_label.as_deref(),
+        || Arc::clone(shared_backtrace()),
+    );
+    let Some(index) = catching_frame else {
+        #[cfg(feature = "tracing")]
+        tracing_integration::record_panic(info, false, || {
+            tracing_integration::captured_backtrace(uncaught::backtrace(&backtrace))
+        });
+        uncaught::handle_uncaught_panic(info, &backtrace);
+        return;
+    };
+
+    // Like filters, the reporter runs without the stack borrowed
+    if let Some((reporter, tags)) = reporter {
+        let mut report = PanicReport::from_hook_info(info, Arc::clone(shared_backtrace()));
+        report.tags = tags.as_deref().map_or_else(Vec::new, <[_]>::to_vec);
+        reporter.report(&report);
+    }
+
+    // Looking up the current span and recording the event call into the `tracing` subscriber, so
+    // also do them without the stack borrowed
+    #[cfg(feature = "tracing")]
+    let context = tracing_integration::current_span_context();
+    #[cfg(feature = "tracing")]
+    tracing_integration::record_panic(info, true, || {
+        tracing_integration::captured_backtrace(shared_backtrace())
+    });
+
+    // Hand the backtrace over to the `catch` call. It is only captured again if a subscriber or
+    // reporter kept a reference to it, since `PanicData` owns its backtrace.
+    let backtrace = backtrace.into_inner().map_or_else(
+        || capture_backtrace.capture(),
+        |backtrace| Arc::try_unwrap(backtrace).unwrap_or_else(|_| capture_backtrace.capture()),
+    );
+
+    THREAD_LOCAL_CATCH_STACK.with_borrow_mut(|stack| {
+        let frame = &mut stack[index];
+
+        // Smuggle out the panic location and backtrace, storing them in
+        // `THREAD_LOCAL_CATCH_STACK` to be extracted later.
+        frame.location = info.location().map(PanicLocation::from);
+        frame.backtrace = backtrace;
+        #[cfg(feature = "tracing")]
+        {
+            frame.context = context;
+        }
+    });
+}
+
+/// Returns the index of the frame in `THREAD_LOCAL_CATCH_STACK` whose `chillpill::catch` call will
+/// catch the panic described by `info`, or [`None`] if the panic will not be caught by chillpill.
+///
+/// Frames are checked from the top of the stack down. Every frame above the returned one has a
+/// filter that rejected the panic, and is marked as such.
+fn find_catching_frame(info: &PanicHookInfo<'_>) -> Option<usize> {
+    let depth = THREAD_LOCAL_CATCH_STACK.with_borrow(Vec::len);
+
+    for index in (0..depth).rev() {
+        // Run the filter without the stack borrowed, so it is free to call `chillpill::catch`
+        let filter = THREAD_LOCAL_CATCH_STACK.with_borrow(|stack| stack[index].filter.clone());
+        let accepted = filter.is_none_or(|filter| filter(info));
+
+        THREAD_LOCAL_CATCH_STACK.with_borrow_mut(|stack| stack[index].rejected = !accepted);
+        if accepted {
+            return Some(index);
+        }
     }
+
+    None
 }
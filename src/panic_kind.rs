@@ -0,0 +1,328 @@
+use std::any::Any;
+
+/// A classification of a panic by its cause, for common panics raised by the standard library.
+///
+/// See [`PanicData::kind`].
+///
+/// [`PanicData::kind`]: crate::PanicData::kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PanicKind {
+    /// `Option::unwrap` on a `None` value, or `Result::unwrap` on an `Err` value.
+    Unwrap(UnwrappedValue),
+
+    /// `Result::unwrap_err` on an `Ok` value.
+    UnwrapErr,
+
+    /// Indexing (or slicing) past the end of a slice, array, or [`Vec`].
+    IndexOutOfBounds {
+        /// The index that was out of bounds.
+        index: usize,
+
+        /// The length of the indexed value.
+        len: usize,
+    },
+
+    /// Arithmetic overflow, detected by overflow checks (for example, in debug builds).
+    ArithmeticOverflow,
+
+    /// Division (or remainder) by zero.
+    DivideByZero,
+
+    /// A failed [`assert!`], [`assert_eq!`], or [`assert_ne!`] (or their `debug_` variants)
+    /// assertion.
+    AssertFailed,
+
+    /// An [`unreachable!`] invocation.
+    Unreachable,
+
+    /// A [`todo!`] invocation.
+    Todo,
+
+    /// An [`unimplemented!`] invocation.
+    Unimplemented,
+
+    /// A [`RefCell`](std::cell::RefCell) borrow that conflicts with an existing borrow.
+    RefCellBorrow,
+
+    /// `Result::expect` on an `Err` value.
+    ///
+    /// This is detected by the shape of its message, `"{msg}: {err:?}"`, where the error's
+    /// [`Debug`] output looks like a struct, tuple, or string (for example,
+    /// `"reading config: Os { code: 2, .. }"`). An explicit [`panic!`] with a message of the same
+    /// shape is classified as `Expect` as well.
+    Expect,
+
+    /// Any other panic with a string payload, typically an explicit [`panic!`].
+    ///
+    /// This includes `Option::expect`, since its message is provided entirely by the caller, and
+    /// cannot be told apart from that of an explicit `panic!`.
+    Explicit,
+
+    /// A panic with a payload that is not a string (for example, from [`std::panic::panic_any`]).
+    Custom,
+}
+
+/// The value that was unwrapped, for [`PanicKind::Unwrap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnwrappedValue {
+    /// An `Option`'s `None` value.
+    None,
+
+    /// A `Result`'s `Err` value.
+    Err,
+}
+
+impl PanicKind {
+    /// Returns a short, stable, `snake_case` name for the kind (for example, `"index_out_of_bounds"`),
+    /// suitable for use as a metric label.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unwrap(UnwrappedValue::None) => "unwrap_none",
+            Self::Unwrap(UnwrappedValue::Err) => "unwrap_err_value",
+            Self::UnwrapErr => "unwrap_ok_value",
+            Self::IndexOutOfBounds { .. } => "index_out_of_bounds",
+            Self::ArithmeticOverflow => "arithmetic_overflow",
+            Self::DivideByZero => "divide_by_zero",
+            Self::AssertFailed => "assert_failed",
+            Self::Unreachable => "unreachable",
+            Self::Todo => "todo",
+            Self::Unimplemented => "unimplemented",
+            Self::RefCellBorrow => "refcell_borrow",
+            Self::Expect => "expect",
+            Self::Explicit => "explicit",
+            Self::Custom => "custom",
+        }
+    }
+}
+
+/// Classifies a panic by its payload.
+///
+/// Only `&str` and `String` payloads are classified by their message - any other payload is
+/// [`PanicKind::Custom`].
+pub(crate) fn classify(payload: &(dyn Any + Send)) -> PanicKind {
+    std_message(payload).map_or(PanicKind::Custom, classify_message)
+}
+
+/// Returns the message of a panic payload that may have been raised by the standard library (that
+/// is, a `&str` or `String` payload).
+pub(crate) fn std_message(payload: &(dyn Any + Send)) -> Option<&str> {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        return Some(s);
+    }
+
+    payload.downcast_ref::<String>().map(String::as_str)
+}
+
+/// Classifies a panic by its message, matching the messages the standard library panics with.
+fn classify_message(message: &str) -> PanicKind {
+    /// Returns whether `message` is `prefix`, optionally followed by `": "` and a custom message.
+    fn is_with_optional_message(message: &str, prefix: &str) -> bool {
+        message
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(": "))
+    }
+
+    if message == "called `Option::unwrap()` on a `None` value" {
+        return PanicKind::Unwrap(UnwrappedValue::None);
+    }
+    if message.starts_with("called `Result::unwrap()` on an `Err` value: ") {
+        return PanicKind::Unwrap(UnwrappedValue::Err);
+    }
+    if message.starts_with("called `Result::unwrap_err()` on an `Ok` value: ") {
+        return PanicKind::UnwrapErr;
+    }
+    if let Some((index, len)) = parse_index_out_of_bounds(message) {
+        return PanicKind::IndexOutOfBounds { index, len };
+    }
+    if message == "attempt to divide by zero"
+        || message == "attempt to calculate the remainder with a divisor of zero"
+    {
+        return PanicKind::DivideByZero;
+    }
+    if message.starts_with("attempt to ") && message.ends_with(" with overflow") {
+        return PanicKind::ArithmeticOverflow;
+    }
+    if message.starts_with("assertion failed: ")
+        || (message.starts_with("assertion `left ") && message.contains(" right` failed"))
+    {
+        return PanicKind::AssertFailed;
+    }
+    if is_with_optional_message(message, "internal error: entered unreachable code") {
+        return PanicKind::Unreachable;
+    }
+    if is_with_optional_message(message, "not yet implemented") {
+        return PanicKind::Todo;
+    }
+    if is_with_optional_message(message, "not implemented") {
+        return PanicKind::Unimplemented;
+    }
+    if matches!(
+        message,
+        "RefCell already borrowed"
+            | "RefCell already mutably borrowed"
+            | "already borrowed: BorrowMutError"
+            | "already mutably borrowed: BorrowError"
+    ) {
+        return PanicKind::RefCellBorrow;
+    }
+    if is_expect_message(message) {
+        return PanicKind::Expect;
+    }
+
+    PanicKind::Explicit
+}
+
+/// Returns whether `message` looks like that of `Result::expect`, `"{msg}: {err:?}"`.
+///
+/// Since both parts may contain `": "`, every split is tried.
+fn is_expect_message(message: &str) -> bool {
+    /// Returns whether `value` looks like [`Debug`] output for a struct, tuple struct, enum
+    /// variant with fields, or string.
+    fn looks_like_debug(value: &str) -> bool {
+        let is_quoted = value.len() >= 2 && value.starts_with('"') && value.ends_with('"');
+        let is_constructed = value.starts_with(|c: char| c.is_ascii_uppercase())
+            && (value.ends_with(')') || value.ends_with('}'));
+
+        is_quoted || is_constructed
+    }
+
+    message
+        .match_indices(": ")
+        .any(|(i, separator)| i > 0 && looks_like_debug(&message[i + separator.len()..]))
+}
+
+/// Parses the index and length out of an out of bounds indexing or slicing message.
+fn parse_index_out_of_bounds(message: &str) -> Option<(usize, usize)> {
+    // Indexing: "index out of bounds: the len is {len} but the index is {index}"
+    if let Some(rest) = message.strip_prefix("index out of bounds: the len is ") {
+        let (len, index) = rest.split_once(" but the index is ")?;
+        return Some((index.parse().ok()?, len.parse().ok()?));
+    }
+
+    // Slicing: "range {start,end} index {index} out of range for slice of length {len}"
+    let rest = message
+        .strip_prefix("range start index ")
+        .or_else(|| message.strip_prefix("range end index "))?;
+    let (index, len) = rest.split_once(" out of range for slice of length ")?;
+    Some((index.parse().ok()?, len.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    use crate::catch;
+
+    /// This test ensures panics raised by the standard library are classified correctly.
+    #[test]
+    fn classifies_std_panics() {
+        #[expect(
+            clippy::unnecessary_literal_unwrap,
+            clippy::todo,
+            reason = "testing the panics"
+        )]
+        let cases: [(fn(), PanicKind); 18] = [
+            (
+                || {
+                    let _ = None::<u8>.unwrap();
+                },
+                PanicKind::Unwrap(UnwrappedValue::None),
+            ),
+            (
+                || {
+                    let _ = Err::<u8, _>("e").unwrap();
+                },
+                PanicKind::Unwrap(UnwrappedValue::Err),
+            ),
+            (
+                || {
+                    let _ = Ok::<_, u8>(1).unwrap_err();
+                },
+                PanicKind::UnwrapErr,
+            ),
+            (
+                || {
+                    let _ = std::hint::black_box(vec![1])[5];
+                },
+                PanicKind::IndexOutOfBounds { index: 5, len: 1 },
+            ),
+            (
+                || {
+                    let _ = &std::hint::black_box(vec![1])[..3];
+                },
+                PanicKind::IndexOutOfBounds { index: 3, len: 1 },
+            ),
+            (
+                || {
+                    let _ = std::hint::black_box(u8::MAX) + 1;
+                },
+                PanicKind::ArithmeticOverflow,
+            ),
+            (
+                || {
+                    let _ = 1 / std::hint::black_box(0);
+                },
+                PanicKind::DivideByZero,
+            ),
+            (
+                || {
+                    let _ = 1 % std::hint::black_box(0);
+                },
+                PanicKind::DivideByZero,
+            ),
+            (
+                || assert!(std::hint::black_box(false)),
+                PanicKind::AssertFailed,
+            ),
+            (
+                || assert_eq!(std::hint::black_box(1), 2, "custom"),
+                PanicKind::AssertFailed,
+            ),
+            (|| unreachable!("y {}", 1), PanicKind::Unreachable),
+            (|| todo!(), PanicKind::Todo),
+            (|| unimplemented!("x"), PanicKind::Unimplemented),
+            (
+                || {
+                    let cell = RefCell::new(0);
+                    let _borrow = cell.borrow();
+                    drop(cell.borrow_mut());
+                },
+                PanicKind::RefCellBorrow,
+            ),
+            (
+                || {
+                    let _ = "x".parse::<u8>().expect("parsing");
+                },
+                PanicKind::Expect,
+            ),
+            (
+                || {
+                    let _ = Err::<u8, _>("disk full").expect("saving: retrying");
+                },
+                PanicKind::Expect,
+            ),
+            (
+                || {
+                    let _ = None::<u8>.expect("value: missing");
+                },
+                PanicKind::Explicit,
+            ),
+            (|| panic!("something broke"), PanicKind::Explicit),
+        ];
+
+        for (f, expected) in cases {
+            let panic_data = catch(f).unwrap_err();
+            assert_eq!(panic_data.kind(), expected, "{panic_data:?}");
+        }
+    }
+
+    /// This test ensures panics with non-string payloads are classified as custom.
+    #[test]
+    fn classifies_custom_payloads() {
+        let panic_data = catch(|| std::panic::panic_any(42_u8)).unwrap_err();
+
+        assert_eq!(panic_data.kind(), PanicKind::Custom);
+    }
+}
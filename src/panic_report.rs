@@ -0,0 +1,272 @@
+use std::{
+    backtrace::Backtrace,
+    borrow::Cow,
+    panic::PanicHookInfo,
+    sync::Arc,
+    thread::{Thread, ThreadId},
+    time::SystemTime,
+};
+
+use crate::{
+    Fingerprint, PanicData, PanicLocation, StructuredPayload, json_lines,
+    payload_formatter::{payload_to_string, payload_type_name},
+    sentry,
+};
+
+/// A report of a panic, produced from within the panic hook.
+///
+/// Unlike [`PanicData`], a `PanicReport` does not own the panic payload (which is still needed to
+/// unwind the panicking thread). Instead, it holds the payload's string representation, if it has
+/// one.
+///
+/// [`PanicData`]: crate::PanicData
+#[derive(Debug, Clone)]
+pub struct PanicReport {
+    /// The panic payload as a string (see [`PanicData::payload_as_string`]), or [`None`] if the
+    /// payload has no string representation.
+    ///
+    /// [`PanicData::payload_as_string`]: crate::PanicData::payload_as_string
+    pub message: Option<String>,
+
+    /// The name of the type of the panic payload (see [`PanicData::payload_type_name`]), or
+    /// [`None`] if the payload has no string representation.
+    ///
+    /// [`PanicData::payload_type_name`]: crate::PanicData::payload_type_name
+    pub payload_type: Option<&'static str>,
+
+    /// Key-value context about the panic: the context of where it happened (see
+    /// [`PanicData::context`]), followed by the fields of a [`StructuredPayload`] payload.
+    ///
+    /// [`PanicData::context`]: crate::PanicData::context
+    /// [`StructuredPayload`]: crate::StructuredPayload
+    pub context: Vec<(String, String)>,
+
+    /// Key-value tags of the [`Catcher`] that caught the panic (see [`Catcher::tag`]). Empty if the
+    /// panic was not caught by a `Catcher`.
+    ///
+    /// [`Catcher`]: crate::Catcher
+    /// [`Catcher::tag`]: crate::Catcher::tag
+    pub tags: Vec<(String, String)>,
+
+    /// The source code location of the panic, or [`None`] if no source location was available.
+    pub location: Option<PanicLocation>,
+
+    /// A backtrace captured at the time of the panic (specifically, within the panic hook).
+    ///
+    /// Whether or not a backtrace is actually captured depends on environment variable
+    /// configuration, like [`Backtrace::capture`].
+    pub backtrace: Arc<Backtrace>,
+
+    /// The thread that panicked.
+    pub thread: ThreadInfo,
+
+    /// The time at which the panic occurred.
+    pub timestamp: SystemTime,
+}
+
+impl PanicReport {
+    /// Creates a report for the panic described by `info`, which is currently being handled by the
+    /// panic hook on this thread.
+    pub(crate) fn from_hook_info(info: &PanicHookInfo<'_>, backtrace: Arc<Backtrace>) -> Self {
+        Self {
+            message: payload_to_string(info.payload()).map(Cow::into_owned),
+            payload_type: payload_type_name(info.payload()),
+            context: hook_context(info),
+            tags: Vec::new(),
+            location: info.location().map(PanicLocation::from),
+            backtrace,
+            thread: ThreadInfo::from(&std::thread::current()),
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    /// Computes a stable fingerprint of the panic from its location and normalized message, like
+    /// [`PanicData::fingerprint`].
+    ///
+    /// [`PanicData::fingerprint`]: crate::PanicData::fingerprint
+    pub fn fingerprint(&self) -> Fingerprint {
+        Fingerprint::compute(self.location.as_ref(), self.message.as_deref(), &[])
+    }
+
+    /// Serializes the report as a single line of JSON, terminated by a newline, for logging in the
+    /// [JSON Lines](https://jsonlines.org) format.
+    ///
+    /// The object has the following members, in this order. The schema is versioned: members may be
+    /// added in a later version of chillpill without changing `schema_version`, but any other change
+    /// increments it.
+    ///
+    /// | Member           | Type             | Description                                                |
+    /// |------------------|------------------|------------------------------------------------------------|
+    /// | `schema_version` | number           | The version of this schema, currently `1`                  |
+    /// | `timestamp_ms`   | number           | [`timestamp`], in milliseconds since the Unix epoch        |
+    /// | `thread`         | object           | `name` ([`thread`]'s name, or `null`) and `id` (a string)  |
+    /// | `payload`        | string or `null` | [`message`]                                                |
+    /// | `payload_type`   | string or `null` | [`payload_type`]                                           |
+    /// | `location`       | object or `null` | [`location`], as `file` (string), `line`, and `col`        |
+    /// | `backtrace`      | array or `null`  | The frames of [`backtrace`], or `null` if not captured     |
+    /// | `context`        | object           | [`context`], mapping keys to string values                 |
+    /// | `tags`           | object           | [`tags`], mapping keys to string values                    |
+    /// | `fingerprint`    | string           | [`fingerprint`], as 16 hexadecimal digits                  |
+    ///
+    /// Each backtrace frame is an object with a `symbol` (string) and `location` (a string like
+    /// `"src/main.rs:3:5"`, or `null`). If a key appears more than once in `context` or `tags`, the
+    /// last occurrence takes precedence.
+    ///
+    /// With the `serde` feature enabled, `PanicReport` also implements `serde::Serialize`, using
+    /// the same schema.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    ///
+    /// use chillpill::{Catcher, MemoryReporter};
+    ///
+    /// let reporter = Arc::new(MemoryReporter::new());
+    /// Catcher::new()
+    ///     .tag("service", "billing")
+    ///     .reporter(Arc::clone(&reporter))
+    ///     .catch(|| panic!("oh no"))
+    ///     .unwrap_err();
+    ///
+    /// let line = reporter.reports()[0].to_json_line();
+    /// assert!(line.starts_with(r#"{"schema_version":1,"timestamp_ms":"#));
+    /// assert!(line.contains(r#""payload":"oh no","payload_type":"&str""#));
+    /// assert!(line.contains(r#""tags":{"service":"billing"}"#));
+    /// ```
+    ///
+    /// [`timestamp`]: PanicReport::timestamp
+    /// [`thread`]: PanicReport::thread
+    /// [`message`]: PanicReport::message
+    /// [`payload_type`]: PanicReport::payload_type
+    /// [`location`]: PanicReport::location
+    /// [`backtrace`]: PanicReport::backtrace
+    /// [`context`]: PanicReport::context
+    /// [`tags`]: PanicReport::tags
+    /// [`fingerprint`]: PanicReport::fingerprint
+    pub fn to_json_line(&self) -> String {
+        let mut out = String::new();
+        json_lines::write_report(&mut out, self);
+        out.push('\n');
+        out
+    }
+
+    /// Serializes the report as a [Sentry event](https://develop.sentry.dev/sdk/data-model/event-payloads/)
+    /// payload, as JSON.
+    ///
+    /// This only produces the payload - sending it (for example, to a Sentry relay) is up to the
+    /// caller. The report maps to the event as follows:
+    ///
+    /// - [`message`] becomes the value of an exception of type `panic`, with a `panic` mechanism
+    ///   carrying [`payload_type`] (if any) in its data.
+    /// - [`backtrace`] (if captured) becomes the exception's stack trace, with the frames of the
+    ///   panic machinery removed. Otherwise, [`location`] (if any) becomes its only frame. Frames
+    ///   in the user's crates (those with a relative source path) are marked as in-app.
+    /// - [`thread`] becomes the only entry of `threads`, marked as crashed.
+    /// - [`tags`] become the event's `tags`, and [`context`] becomes its `extra` data.
+    /// - [`fingerprint`] becomes the event's `fingerprint`, so Sentry groups events like
+    ///   [`PanicGrouper`] does.
+    ///
+    /// Each call generates a new random `event_id`, and the event's `level` is `error`.
+    ///
+    /// [`message`]: PanicReport::message
+    /// [`payload_type`]: PanicReport::payload_type
+    /// [`backtrace`]: PanicReport::backtrace
+    /// [`location`]: PanicReport::location
+    /// [`thread`]: PanicReport::thread
+    /// [`tags`]: PanicReport::tags
+    /// [`context`]: PanicReport::context
+    /// [`fingerprint`]: PanicReport::fingerprint
+    /// [`PanicGrouper`]: crate::PanicGrouper
+    pub fn to_sentry_event(&self) -> String {
+        let mut out = String::new();
+        sentry::write_event(&mut out, self, &sentry::new_event_id());
+        out
+    }
+
+    /// Returns a snapshot of this report, without the backtrace.
+    pub fn snapshot(&self) -> PanicSnapshot {
+        PanicSnapshot {
+            message: self.message.clone(),
+            location: self.location.clone(),
+            thread: self.thread.clone(),
+            timestamp: self.timestamp,
+        }
+    }
+}
+
+/// Returns the context of the panic described by `info`, which is currently being handled by the
+/// panic hook on this thread (see [`PanicReport::context`]).
+pub(crate) fn hook_context(info: &PanicHookInfo<'_>) -> Vec<(String, String)> {
+    #[cfg(feature = "tracing")]
+    let mut context = crate::tracing_integration::current_span_context();
+    #[cfg(not(feature = "tracing"))]
+    let mut context = Vec::new();
+
+    if let Some(payload) = info.payload().downcast_ref::<StructuredPayload>() {
+        context.extend_from_slice(payload.fields());
+    }
+
+    context
+}
+
+/// Identifying information about a thread.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ThreadInfo {
+    /// The name of the thread, or [`None`] if it is unnamed.
+    pub name: Option<String>,
+
+    /// The unique identifier of the thread.
+    pub id: ThreadId,
+}
+
+impl From<&Thread> for ThreadInfo {
+    fn from(thread: &Thread) -> Self {
+        Self {
+            name: thread.name().map(String::from),
+            id: thread.id(),
+        }
+    }
+}
+
+/// A lightweight record of a panic, without a backtrace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanicSnapshot {
+    /// The panic payload as a string (see [`PanicData::payload_as_string`]), or [`None`] if the
+    /// payload has no string representation.
+    ///
+    /// [`PanicData::payload_as_string`]: crate::PanicData::payload_as_string
+    pub message: Option<String>,
+
+    /// The source code location of the panic, or [`None`] if no source location was available.
+    pub location: Option<PanicLocation>,
+
+    /// The thread that panicked.
+    pub thread: ThreadInfo,
+
+    /// The time at which the panic occurred.
+    pub timestamp: SystemTime,
+}
+
+impl PanicSnapshot {
+    /// Creates a snapshot of a caught panic, as if it happened on this thread just now.
+    pub(crate) fn from_panic_data(panic_data: &PanicData) -> Self {
+        Self {
+            message: panic_data.payload_as_string().map(Cow::into_owned),
+            location: panic_data.location.clone(),
+            thread: ThreadInfo::from(&std::thread::current()),
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    /// Creates a snapshot of the panic described by `info`, which is currently being handled by the
+    /// panic hook on this thread.
+    pub(crate) fn from_hook_info(info: &PanicHookInfo<'_>) -> Self {
+        Self {
+            message: payload_to_string(info.payload()).map(Cow::into_owned),
+            location: info.location().map(PanicLocation::from),
+            thread: ThreadInfo::from(&std::thread::current()),
+            timestamp: SystemTime::now(),
+        }
+    }
+}
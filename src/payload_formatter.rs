@@ -0,0 +1,236 @@
+use std::{
+    any::{Any, TypeId},
+    borrow::Cow,
+    error::Error,
+    fmt::{Debug, Display, Write},
+    sync::{PoisonError, RwLock},
+};
+
+use crate::{FatalPanic, StructuredPayload, panic_data::payload_as_str};
+
+type PayloadFormatter = Box<dyn Fn(&(dyn Any + Send)) -> Option<String> + Send + Sync>;
+
+/// A registered payload formatter, along with the type of payload it formats.
+struct Registration {
+    type_id: TypeId,
+    type_name: &'static str,
+    formatter: PayloadFormatter,
+}
+
+/// The registered payload formatters.
+///
+/// This is a [`Vec`] rather than a map so that it can be created in a `static`, and because only a
+/// handful of payload types are ever expected to be registered.
+static PAYLOAD_FORMATTERS: RwLock<Vec<Registration>> = RwLock::new(Vec::new());
+
+/// Registers a function that formats panic payloads of type `T` as a string, replacing any
+/// formatter previously registered for `T`.
+///
+/// Out of the box, chillpill only knows how to turn `&str` and `String` payloads (and its own
+/// [`FatalPanic`] and [`StructuredPayload`]) into a message, and any other payload shows up as an
+/// opaque [`Any`]. Once a formatter is registered for a payload type, panics with that payload are
+/// given a message everywhere chillpill produces one: [`PanicData::payload_as_string`],
+/// [`PanicData`]'s [`Debug`] impl, [`PanicReport::message`], and crash reports.
+///
+/// See also [`register_display_payload`], [`register_debug_payload`], and
+/// [`register_error_payload`], which register a formatter based on a trait the payload implements.
+///
+/// The formatter may be called inside the panic hook, so it must not panic (doing so aborts the
+/// process), and must not register a payload formatter itself (doing so deadlocks).
+///
+/// # Example
+///
+/// ```
+/// struct Timeout {
+///     millis: u64,
+/// }
+///
+/// chillpill::register_payload_formatter::<Timeout>(|timeout| {
+///     format!("timed out after {}ms", timeout.millis)
+/// });
+///
+/// let panic_data = chillpill::catch(|| std::panic::panic_any(Timeout { millis: 250 })).unwrap_err();
+/// assert_eq!(
+///     panic_data.payload_as_string().as_deref(),
+///     Some("timed out after 250ms")
+/// );
+/// ```
+///
+/// [`FatalPanic`]: crate::FatalPanic
+/// [`StructuredPayload`]: crate::StructuredPayload
+/// [`PanicData`]: crate::PanicData
+/// [`PanicData::payload_as_string`]: crate::PanicData::payload_as_string
+/// [`PanicReport::message`]: crate::PanicReport::message
+pub fn register_payload_formatter<T: Any + Send>(
+    formatter: impl Fn(&T) -> String + Send + Sync + 'static,
+) {
+    let registration = Registration {
+        type_id: TypeId::of::<T>(),
+        type_name: std::any::type_name::<T>(),
+        formatter: Box::new(move |payload| payload.downcast_ref::<T>().map(&formatter)),
+    };
+
+    let mut formatters = PAYLOAD_FORMATTERS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner);
+    match formatters
+        .iter_mut()
+        .find(|existing| existing.type_id == registration.type_id)
+    {
+        Some(existing) => *existing = registration,
+        None => formatters.push(registration),
+    }
+}
+
+/// Registers a formatter for panic payloads of type `T` using its [`Display`] impl.
+///
+/// See [`register_payload_formatter`].
+pub fn register_display_payload<T: Any + Send + Display>() {
+    register_payload_formatter::<T>(ToString::to_string);
+}
+
+/// Registers a formatter for panic payloads of type `T` using its [`Debug`] impl.
+///
+/// See [`register_payload_formatter`].
+pub fn register_debug_payload<T: Any + Send + Debug>() {
+    register_payload_formatter::<T>(|payload| format!("{payload:?}"));
+}
+
+/// Registers a formatter for panic payloads of type `T` using its [`Error`] impl, including its
+/// chain of sources (for example, `"request failed: connection reset"`).
+///
+/// See [`register_payload_formatter`].
+pub fn register_error_payload<T: Any + Send + Error>() {
+    register_payload_formatter::<T>(|payload| {
+        let mut out = payload.to_string();
+        let mut source = payload.source();
+        while let Some(error) = source {
+            let _ = write!(out, ": {error}");
+            source = error.source();
+        }
+        out
+    });
+}
+
+/// Converts a panic payload to a string, using the registered payload formatters for payloads that
+/// are not already strings (see [`payload_as_str`]). Returns [`None`] if no formatter applies.
+pub(crate) fn payload_to_string(payload: &(dyn Any + Send)) -> Option<Cow<'_, str>> {
+    if let Some(s) = payload_as_str(payload) {
+        return Some(Cow::Borrowed(s));
+    }
+
+    let type_id = (*payload).type_id();
+    let formatters = PAYLOAD_FORMATTERS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner);
+    formatters
+        .iter()
+        .find(|registration| registration.type_id == type_id)
+        .and_then(|registration| (registration.formatter)(payload))
+        .map(Cow::Owned)
+}
+
+/// Returns the name of the type of a panic payload, if it is one chillpill knows how to turn into a
+/// string (see [`payload_to_string`]). Returns [`None`] for any other payload.
+///
+/// Registered payload types are named by [`std::any::type_name`].
+pub(crate) fn payload_type_name(payload: &(dyn Any + Send)) -> Option<&'static str> {
+    if payload.is::<&str>() {
+        return Some("&str");
+    }
+    if payload.is::<String>() {
+        return Some("String");
+    }
+    if payload.is::<FatalPanic>() {
+        return Some("chillpill::FatalPanic");
+    }
+    if payload.is::<StructuredPayload>() {
+        return Some("chillpill::StructuredPayload");
+    }
+
+    let type_id = (*payload).type_id();
+    let formatters = PAYLOAD_FORMATTERS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner);
+    formatters
+        .iter()
+        .find(|registration| registration.type_id == type_id)
+        .map(|registration| registration.type_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// This test ensures string payloads are borrowed, and unregistered payloads have no string.
+    #[test]
+    fn strings_and_unregistered() {
+        struct Unregistered;
+
+        assert!(matches!(
+            payload_to_string(&"message"),
+            Some(Cow::Borrowed("message"))
+        ));
+        assert_eq!(payload_to_string(&Unregistered), None);
+        assert_eq!(payload_type_name(&"message"), Some("&str"));
+        assert_eq!(payload_type_name(&Unregistered), None);
+    }
+
+    /// This test ensures a registered formatter is used, and that registering again replaces it.
+    #[test]
+    fn registered_formatter_replaced() {
+        struct Custom(u8);
+
+        register_payload_formatter::<Custom>(|custom| format!("custom {}", custom.0));
+        assert_eq!(payload_to_string(&Custom(1)).as_deref(), Some("custom 1"));
+        assert_eq!(
+            payload_type_name(&Custom(1)),
+            Some(std::any::type_name::<Custom>())
+        );
+
+        register_payload_formatter::<Custom>(|custom| format!("replaced {}", custom.0));
+        assert_eq!(payload_to_string(&Custom(2)).as_deref(), Some("replaced 2"));
+    }
+
+    /// This test ensures the [`Display`], [`Debug`], and [`Error`] formatters.
+    #[test]
+    fn trait_formatters() {
+        #[derive(Debug)]
+        struct DebugOnly {
+            #[expect(dead_code, reason = "only read through the `Debug` impl")]
+            id: u32,
+        }
+
+        #[derive(Debug)]
+        struct Outer(std::io::Error);
+
+        impl Display for Outer {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "request failed")
+            }
+        }
+
+        impl Error for Outer {
+            fn source(&self) -> Option<&(dyn Error + 'static)> {
+                Some(&self.0)
+            }
+        }
+
+        register_display_payload::<std::net::Ipv4Addr>();
+        register_debug_payload::<DebugOnly>();
+        register_error_payload::<Outer>();
+
+        assert_eq!(
+            payload_to_string(&std::net::Ipv4Addr::LOCALHOST).as_deref(),
+            Some("127.0.0.1")
+        );
+        assert_eq!(
+            payload_to_string(&DebugOnly { id: 3 }).as_deref(),
+            Some("DebugOnly { id: 3 }")
+        );
+        assert_eq!(
+            payload_to_string(&Outer(std::io::Error::other("connection reset"))).as_deref(),
+            Some("request failed: connection reset")
+        );
+    }
+}
@@ -0,0 +1,333 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{
+    PanicCounts, PanicLocation, PanicStats,
+    stats::{self, DURATION_BUCKETS, DurationHistogram},
+};
+
+/// The label value used for panics at locations beyond the cardinality limit.
+const OTHER_LABEL: &str = "other";
+
+/// Renders the panic statistics (see [`chillpill::stats`]) in the Prometheus text exposition
+/// format, for serving from a metrics endpoint.
+///
+/// The following metrics are rendered:
+/// - `chillpill_panics_total`, a counter of panics by `caught` (`"true"` or `"false"`), `file`, and
+///   `line`.
+/// - `chillpill_catch_panic_duration_seconds`, a histogram (by `file` and `line`) of how long each
+///   `chillpill::catch` call ran before catching a panic. This is only rendered if enabled with
+///   [`PrometheusRenderer::catch_duration_histograms`].
+///
+/// To bound the number of time series, only the locations with the most panics get their own
+/// labels (see [`PrometheusRenderer::max_locations`]). Panics at all other locations (and panics
+/// without a location) are counted under `file="other",line="other"`.
+///
+/// chillpill does no networking itself - serving the rendered text is up to you.
+///
+/// # Example
+///
+/// ```
+/// chillpill::catch(|| panic!("oh no")).unwrap_err();
+///
+/// let metrics = chillpill::PrometheusRenderer::new().max_locations(20).render();
+/// assert!(metrics.contains("chillpill_panics_total{caught=\"true\","));
+/// ```
+///
+/// [`chillpill::stats`]: crate::stats()
+#[derive(Debug, Clone)]
+pub struct PrometheusRenderer {
+    max_locations: usize,
+    catch_duration_histograms: bool,
+}
+
+impl PrometheusRenderer {
+    /// Creates a renderer with a limit of 100 locations, and without histograms.
+    pub fn new() -> Self {
+        Self {
+            max_locations: 100,
+            catch_duration_histograms: false,
+        }
+    }
+
+    /// Sets the maximum number of distinct locations to render labels for.
+    #[must_use]
+    pub fn max_locations(mut self, max_locations: usize) -> Self {
+        self.max_locations = max_locations;
+        self
+    }
+
+    /// Sets whether to render histograms of how long each `chillpill::catch` call ran before
+    /// catching a panic, by location.
+    ///
+    /// Timing `catch` calls has a small cost, so durations are only recorded once this has been
+    /// enabled (on any renderer) - enable it at startup to include every panic.
+    #[must_use]
+    pub fn catch_duration_histograms(mut self, catch_duration_histograms: bool) -> Self {
+        if catch_duration_histograms {
+            stats::enable_catch_durations();
+        }
+        self.catch_duration_histograms = catch_duration_histograms;
+        self
+    }
+
+    /// Renders the current panic statistics.
+    pub fn render(&self) -> String {
+        let catch_durations = self.catch_duration_histograms.then(stats::catch_durations);
+
+        self.render_stats(&stats::stats(), catch_durations.as_ref())
+    }
+
+    fn render_stats(
+        &self,
+        stats: &PanicStats,
+        catch_durations: Option<&HashMap<PanicLocation, DurationHistogram>>,
+    ) -> String {
+        let mut out = String::new();
+
+        // Panics are labeled by file and line only, so merge locations differing by column
+        let mut counts: HashMap<(&str, u32), PanicCounts> = HashMap::new();
+        for (location, location_counts) in &stats.by_location {
+            let entry = counts.entry((&location.file, location.line)).or_default();
+            entry.caught += location_counts.caught;
+            entry.uncaught += location_counts.uncaught;
+        }
+
+        // Keep the locations with the most panics, and render them in a stable order
+        let mut labeled: Vec<((&str, u32), PanicCounts)> = counts.into_iter().collect();
+        labeled.sort_by(|(a_key, a_counts), (b_key, b_counts)| {
+            b_counts
+                .total()
+                .cmp(&a_counts.total())
+                .then(a_key.cmp(b_key))
+        });
+        labeled.truncate(self.max_locations);
+        labeled.sort_by_key(|(key, _)| *key);
+
+        let mut other = stats.total;
+        let _ = writeln!(
+            out,
+            "# HELP chillpill_panics_total Panics seen by the chillpill panic hook."
+        );
+        let _ = writeln!(out, "# TYPE chillpill_panics_total counter");
+        for ((file, line), counts) in &labeled {
+            other.caught = other.caught.saturating_sub(counts.caught);
+            other.uncaught = other.uncaught.saturating_sub(counts.uncaught);
+            write_panic_counts(
+                &mut out,
+                &escape_label_value(file),
+                &line.to_string(),
+                counts,
+            );
+        }
+        if other.total() > 0 {
+            write_panic_counts(&mut out, OTHER_LABEL, OTHER_LABEL, &other);
+        }
+
+        let Some(catch_durations) = catch_durations else {
+            return out;
+        };
+
+        let mut histograms: HashMap<Option<(&str, u32)>, DurationHistogram> = HashMap::new();
+        for (location, histogram) in catch_durations {
+            let key = (location.file.as_str(), location.line);
+            let key = labeled
+                .iter()
+                .any(|(labeled_key, _)| *labeled_key == key)
+                .then_some(key);
+            histograms.entry(key).or_default().merge(histogram);
+        }
+        let mut histograms: Vec<_> = histograms.into_iter().collect();
+        histograms.sort_by(|(a_key, _), (b_key, _)| {
+            // Render the other locations last
+            a_key.is_none().cmp(&b_key.is_none()).then(a_key.cmp(b_key))
+        });
+
+        let _ = writeln!(
+            out,
+            "# HELP chillpill_catch_panic_duration_seconds Time from the start of a chillpill catch to a panic it caught."
+        );
+        let _ = writeln!(
+            out,
+            "# TYPE chillpill_catch_panic_duration_seconds histogram"
+        );
+        for (key, histogram) in &histograms {
+            let labels = match key {
+                Some((file, line)) => {
+                    format!("file=\"{}\",line=\"{line}\"", escape_label_value(file))
+                }
+                None => format!("file=\"{OTHER_LABEL}\",line=\"{OTHER_LABEL}\""),
+            };
+
+            let mut cumulative = 0;
+            for (bound, count) in DURATION_BUCKETS.iter().zip(histogram.buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "chillpill_catch_panic_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {cumulative}"
+                );
+            }
+            cumulative += histogram.buckets[DURATION_BUCKETS.len()];
+            let _ = writeln!(
+                out,
+                "chillpill_catch_panic_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {cumulative}"
+            );
+            let _ = writeln!(
+                out,
+                "chillpill_catch_panic_duration_seconds_sum{{{labels}}} {}",
+                histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "chillpill_catch_panic_duration_seconds_count{{{labels}}} {cumulative}"
+            );
+        }
+
+        out
+    }
+}
+
+impl Default for PrometheusRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Writes the `chillpill_panics_total` samples for one location.
+fn write_panic_counts(out: &mut String, file: &str, line: &str, counts: &PanicCounts) {
+    for (caught, count) in [("true", counts.caught), ("false", counts.uncaught)] {
+        let _ = writeln!(
+            out,
+            "chillpill_panics_total{{caught=\"{caught}\",file=\"{file}\",line=\"{line}\"}} {count}"
+        );
+    }
+}
+
+/// Escapes a label value for the Prometheus text exposition format.
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    /// Helper function to create a location in `file` at `line`.
+    fn location(file: &str, line: u32, col: u32) -> PanicLocation {
+        PanicLocation {
+            file: String::from(file),
+            line,
+            col,
+        }
+    }
+
+    /// Helper function to create statistics with the given counts by location.
+    fn make_stats(
+        by_location: &[(PanicLocation, PanicCounts)],
+        unlocated: PanicCounts,
+    ) -> PanicStats {
+        let mut total = unlocated;
+        for (_, counts) in by_location {
+            total.caught += counts.caught;
+            total.uncaught += counts.uncaught;
+        }
+
+        PanicStats {
+            total,
+            by_location: by_location.iter().cloned().collect(),
+            by_thread: HashMap::new(),
+        }
+    }
+
+    /// This test ensures counters are rendered by location, merging columns, and that locations
+    /// beyond the limit are counted as other locations.
+    #[test]
+    fn renders_counters_with_limit() {
+        let counts = |caught, uncaught| PanicCounts { caught, uncaught };
+        let stats = make_stats(
+            &[
+                (location("src/a.rs", 1, 1), counts(3, 0)),
+                (location("src/a.rs", 1, 5), counts(1, 0)),
+                (location("src/\"b\".rs", 2, 1), counts(0, 2)),
+                (location("src/c.rs", 3, 1), counts(1, 0)),
+            ],
+            counts(0, 1),
+        );
+
+        assert_eq!(
+            PrometheusRenderer::new()
+                .max_locations(2)
+                .render_stats(&stats, None),
+            r#"# HELP chillpill_panics_total Panics seen by the chillpill panic hook.
+# TYPE chillpill_panics_total counter
+chillpill_panics_total{caught="true",file="src/\"b\".rs",line="2"} 0
+chillpill_panics_total{caught="false",file="src/\"b\".rs",line="2"} 2
+chillpill_panics_total{caught="true",file="src/a.rs",line="1"} 4
+chillpill_panics_total{caught="false",file="src/a.rs",line="1"} 0
+chillpill_panics_total{caught="true",file="other",line="other"} 1
+chillpill_panics_total{caught="false",file="other",line="other"} 1
+"#
+        );
+    }
+
+    /// This test ensures histograms are rendered with cumulative buckets.
+    #[test]
+    fn renders_histograms() {
+        let a = location("src/a.rs", 1, 1);
+        let stats = make_stats(
+            &[(
+                a.clone(),
+                PanicCounts {
+                    caught: 2,
+                    uncaught: 0,
+                },
+            )],
+            PanicCounts::default(),
+        );
+        let mut histogram = DurationHistogram::default();
+        histogram.record(Duration::from_millis(5));
+        histogram.record(Duration::from_secs(1000));
+        let catch_durations = HashMap::from([(a, histogram)]);
+
+        let rendered = PrometheusRenderer::new().render_stats(&stats, Some(&catch_durations));
+        let histogram_lines: Vec<&str> = rendered
+            .lines()
+            .filter(|line| line.starts_with("chillpill_catch_panic_duration_seconds"))
+            .collect();
+
+        assert_eq!(
+            histogram_lines,
+            [
+                r#"chillpill_catch_panic_duration_seconds_bucket{file="src/a.rs",line="1",le="0.0001"} 0"#,
+                r#"chillpill_catch_panic_duration_seconds_bucket{file="src/a.rs",line="1",le="0.001"} 0"#,
+                r#"chillpill_catch_panic_duration_seconds_bucket{file="src/a.rs",line="1",le="0.01"} 1"#,
+                r#"chillpill_catch_panic_duration_seconds_bucket{file="src/a.rs",line="1",le="0.1"} 1"#,
+                r#"chillpill_catch_panic_duration_seconds_bucket{file="src/a.rs",line="1",le="1"} 1"#,
+                r#"chillpill_catch_panic_duration_seconds_bucket{file="src/a.rs",line="1",le="10"} 1"#,
+                r#"chillpill_catch_panic_duration_seconds_bucket{file="src/a.rs",line="1",le="100"} 1"#,
+                r#"chillpill_catch_panic_duration_seconds_bucket{file="src/a.rs",line="1",le="+Inf"} 2"#,
+                r#"chillpill_catch_panic_duration_seconds_sum{file="src/a.rs",line="1"} 1000.005"#,
+                r#"chillpill_catch_panic_duration_seconds_count{file="src/a.rs",line="1"} 2"#,
+            ]
+        );
+    }
+
+    /// This test ensures enabling histograms starts recording how long `catch` calls ran.
+    #[test]
+    fn enabling_histograms_records_catch_durations() {
+        let _renderer = PrometheusRenderer::new().catch_duration_histograms(true);
+
+        let expected_line = line!() + 1;
+        crate::catch(|| panic!("timed")).unwrap_err();
+
+        assert!(
+            stats::catch_durations()
+                .keys()
+                .any(|location| location.file == file!() && location.line == expected_line)
+        );
+    }
+}
@@ -0,0 +1,361 @@
+use std::{
+    backtrace::BacktraceStatus,
+    fmt::Write as _,
+    fs::{File, OpenOptions},
+    io::{self, Write as _},
+    path::Path,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use crate::{CrashReportFormat, PanicReport, crash_report::write_snapshot_text};
+
+/// A destination for reports of panics, like a log or an error tracking service.
+///
+/// Reporters can be attached to a [`Catcher`] (see [`Catcher::reporter`]) to report the panics it
+/// catches, and to the uncaught panic path (see [`set_uncaught_reporter`]) to report panics that
+/// nothing catches. Several reporters can be combined with [`FanOut`].
+///
+/// chillpill provides [`StderrReporter`], [`FileReporter`], and [`MemoryReporter`].
+///
+/// [`Catcher`]: crate::Catcher
+/// [`Catcher::reporter`]: crate::Catcher::reporter
+/// [`set_uncaught_reporter`]: crate::set_uncaught_reporter
+pub trait PanicReporter: Send + Sync {
+    /// Reports a panic.
+    ///
+    /// This is called inside the panic hook, so it must not panic - doing so aborts the process.
+    /// Errors (like failing to write to a file) can't be returned, and should be ignored.
+    fn report(&self, report: &PanicReport);
+
+    /// Waits until every report has been delivered, or until `timeout` has passed, returning
+    /// whether every report was delivered.
+    ///
+    /// Reporters that deliver reports in the background (like [`UnixSocketReporter`]) should
+    /// override this, since the process may exit before they get the chance otherwise. For the
+    /// uncaught panic reporter, it is called with a bounded timeout before chillpill aborts the
+    /// process, and when [`chillpill::main`] returns (see [`flush_uncaught_reporter`]). By default,
+    /// reports are assumed to be delivered by [`PanicReporter::report`] itself, and this returns
+    /// `true` immediately.
+    ///
+    /// [`UnixSocketReporter`]: crate::UnixSocketReporter
+    /// [`chillpill::main`]: crate::main()
+    /// [`flush_uncaught_reporter`]: crate::flush_uncaught_reporter
+    fn flush(&self, timeout: Duration) -> bool {
+        let _ = timeout;
+        true
+    }
+}
+
+impl<R: PanicReporter + ?Sized> PanicReporter for Box<R> {
+    fn report(&self, report: &PanicReport) {
+        (**self).report(report);
+    }
+
+    fn flush(&self, timeout: Duration) -> bool {
+        (**self).flush(timeout)
+    }
+}
+
+impl<R: PanicReporter + ?Sized> PanicReporter for Arc<R> {
+    fn report(&self, report: &PanicReport) {
+        (**self).report(report);
+    }
+
+    fn flush(&self, timeout: Duration) -> bool {
+        (**self).flush(timeout)
+    }
+}
+
+/// Renders a report as human-readable text, or as a single line of JSON (see
+/// [`PanicReport::to_json_line`]).
+fn render_report(report: &PanicReport, format: CrashReportFormat) -> String {
+    match format {
+        CrashReportFormat::Text => {
+            let mut out = String::from("panic:\n");
+            write_snapshot_text(&mut out, &report.snapshot());
+            if report.backtrace.status() == BacktraceStatus::Captured {
+                let _ = writeln!(out, "  backtrace:");
+                let _ = writeln!(out, "{}", report.backtrace);
+            }
+            out
+        }
+        CrashReportFormat::Json => report.to_json_line(),
+    }
+}
+
+/// A reporter that writes reports to `stderr`, as human-readable text or as JSON Lines.
+#[derive(Debug, Clone, Copy)]
+pub struct StderrReporter {
+    format: CrashReportFormat,
+}
+
+impl StderrReporter {
+    /// Creates a reporter that writes human-readable text.
+    pub fn new() -> Self {
+        Self {
+            format: CrashReportFormat::Text,
+        }
+    }
+
+    /// Sets the format of the reports. [`CrashReportFormat::Json`] writes one JSON object per line
+    /// (see [`PanicReport::to_json_line`]).
+    #[must_use]
+    pub fn format(mut self, format: CrashReportFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+impl Default for StderrReporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PanicReporter for StderrReporter {
+    fn report(&self, report: &PanicReport) {
+        // Write the whole report at once, so that reports from different threads don't interleave
+        let _ = io::stderr().write_all(render_report(report, self.format).as_bytes());
+    }
+}
+
+/// A reporter that appends reports to a file, as JSON Lines or as human-readable text.
+#[derive(Debug)]
+pub struct FileReporter {
+    file: Mutex<File>,
+    format: CrashReportFormat,
+}
+
+impl FileReporter {
+    /// Opens (or creates) the file at `path` for appending JSON Lines reports (see
+    /// [`PanicReport::to_json_line`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file could not be opened.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(Self {
+            file: Mutex::new(file),
+            format: CrashReportFormat::Json,
+        })
+    }
+
+    /// Sets the format of the reports.
+    #[must_use]
+    pub fn format(mut self, format: CrashReportFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+impl PanicReporter for FileReporter {
+    fn report(&self, report: &PanicReport) {
+        let rendered = render_report(report, self.format);
+        let _ = self
+            .file
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .write_all(rendered.as_bytes());
+    }
+}
+
+/// A reporter that keeps reports in memory, for inspection in tests.
+#[derive(Debug, Default)]
+pub struct MemoryReporter {
+    reports: Mutex<Vec<PanicReport>>,
+}
+
+impl MemoryReporter {
+    /// Creates a reporter with no reports.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns every report so far, in order.
+    pub fn reports(&self) -> Vec<PanicReport> {
+        self.reports
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Removes and returns every report so far, in order.
+    pub fn take_reports(&self) -> Vec<PanicReport> {
+        std::mem::take(&mut *self.reports.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+impl PanicReporter for MemoryReporter {
+    fn report(&self, report: &PanicReport) {
+        self.reports
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(report.clone());
+    }
+}
+
+/// A reporter that passes every report on to several other reporters, in order.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use chillpill::{Catcher, FanOut, MemoryReporter, StderrReporter};
+///
+/// let memory = Arc::new(MemoryReporter::new());
+/// let reporter = FanOut::new()
+///     .with(StderrReporter::new())
+///     .with(Arc::clone(&memory));
+///
+/// Catcher::new()
+///     .reporter(reporter)
+///     .catch(|| panic!("oh no"))
+///     .unwrap_err();
+/// assert_eq!(memory.reports()[0].message.as_deref(), Some("oh no"));
+/// ```
+#[derive(Default)]
+pub struct FanOut {
+    reporters: Vec<Box<dyn PanicReporter>>,
+}
+
+impl std::fmt::Debug for FanOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FanOut")
+            .field("reporters", &self.reporters.len())
+            .finish()
+    }
+}
+
+impl FanOut {
+    /// Creates a reporter that passes reports on to no other reporters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a reporter to pass reports on to.
+    #[must_use]
+    pub fn with(mut self, reporter: impl PanicReporter + 'static) -> Self {
+        self.reporters.push(Box::new(reporter));
+        self
+    }
+}
+
+impl PanicReporter for FanOut {
+    fn report(&self, report: &PanicReport) {
+        for reporter in &self.reporters {
+            reporter.report(report);
+        }
+    }
+
+    fn flush(&self, timeout: Duration) -> bool {
+        // Share the timeout between the reporters, but give each of them the chance to flush
+        let deadline = Instant::now() + timeout;
+        let mut flushed = true;
+        for reporter in &self.reporters {
+            flushed &= reporter.flush(deadline.saturating_duration_since(Instant::now()));
+        }
+
+        flushed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{backtrace::Backtrace, time::SystemTime};
+
+    use super::*;
+
+    use crate::{Catcher, PanicLocation, ThreadInfo};
+
+    /// Helper function to create a report with fixed contents.
+    fn make_report() -> PanicReport {
+        PanicReport {
+            message: Some(String::from("oh \"no\"")),
+            payload_type: Some("String"),
+            context: Vec::new(),
+            tags: Vec::new(),
+            location: Some(PanicLocation {
+                file: String::from("src/main.rs"),
+                line: 3,
+                col: 5,
+            }),
+            backtrace: Arc::new(Backtrace::disabled()),
+            thread: ThreadInfo {
+                name: Some(String::from("main")),
+                id: std::thread::current().id(),
+            },
+            timestamp: SystemTime::UNIX_EPOCH + Duration::from_millis(1500),
+        }
+    }
+
+    /// This test ensures reports are rendered as text and as single JSON lines.
+    #[test]
+    fn renders_reports() {
+        let report = make_report();
+        let thread_id = format!("{:?}", report.thread.id);
+
+        assert_eq!(
+            render_report(&report, CrashReportFormat::Text),
+            format!(
+                "panic:\n  message: oh \"no\"\n  location: src/main.rs:3:5\n  thread: main ({thread_id})\n  time: 1.500 (seconds since the Unix epoch)\n"
+            )
+        );
+        assert_eq!(
+            render_report(&report, CrashReportFormat::Json),
+            report.to_json_line()
+        );
+    }
+
+    /// This test ensures a catcher's reporter receives the panics it catches, through a fan-out.
+    #[test]
+    fn catcher_reports_through_fan_out() {
+        let first = Arc::new(MemoryReporter::new());
+        let second = Arc::new(MemoryReporter::new());
+        let catcher = Catcher::new().reporter(
+            FanOut::new()
+                .with(Arc::clone(&first))
+                .with(Arc::clone(&second)),
+        );
+
+        catcher.catch(|| panic!("reported")).unwrap_err();
+        assert_eq!(catcher.catch(|| 42).unwrap(), 42);
+
+        for reporter in [first, second] {
+            let reports = reporter.take_reports();
+            assert_eq!(reports.len(), 1);
+            assert_eq!(reports[0].message.as_deref(), Some("reported"));
+            assert_eq!(reports[0].location.as_ref().unwrap().file, file!());
+            assert!(reporter.reports().is_empty());
+        }
+    }
+
+    /// This test ensures a file reporter appends one JSON line per report.
+    #[test]
+    fn file_reporter_appends_lines() {
+        let path = std::env::temp_dir().join(format!(
+            "chillpill-file-reporter-{}.jsonl",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        let reporter = FileReporter::open(&path).unwrap();
+        reporter.report(&make_report());
+        reporter.report(&make_report());
+        drop(reporter);
+        FileReporter::open(&path).unwrap().report(&make_report());
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().count(), 3);
+        assert!(
+            contents
+                .lines()
+                .all(|line| line.starts_with("{\"schema_version\":1,"))
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,230 @@
+use std::{
+    panic::{AssertUnwindSafe, UnwindSafe},
+    time::Duration,
+};
+
+use crate::{PanicData, catch_inner, thread_local_catch_stack::CaptureBacktrace};
+
+type RetryPredicate = Box<dyn Fn(&PanicData) -> bool + Send + Sync>;
+
+/// How long to wait between attempts in [`chillpill::retry`].
+///
+/// [`chillpill::retry`]: crate::retry()
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backoff {
+    /// Retry immediately.
+    None,
+
+    /// Wait the same amount of time before every retry.
+    Fixed(Duration),
+
+    /// Wait `initial` before the first retry, and multiply the wait by `factor` before each
+    /// subsequent retry, never waiting longer than `max`.
+    Exponential {
+        /// The wait before the first retry.
+        initial: Duration,
+
+        /// The factor the wait is multiplied by after each retry.
+        factor: u32,
+
+        /// The longest wait before any retry.
+        max: Duration,
+    },
+}
+
+impl Backoff {
+    /// Returns how long to wait before the `retry`th retry (starting at zero).
+    pub(crate) fn delay(self, retry: u32) -> Duration {
+        match self {
+            Self::None => Duration::ZERO,
+            Self::Fixed(delay) => delay,
+            Self::Exponential {
+                initial,
+                factor,
+                max,
+            } => factor
+                .checked_pow(retry)
+                .and_then(|multiplier| initial.checked_mul(multiplier))
+                .map_or(max, |delay| delay.min(max)),
+        }
+    }
+}
+
+/// A policy controlling how [`chillpill::retry`] re-runs a panicking closure.
+///
+/// [`chillpill::retry`]: crate::retry()
+pub struct RetryPolicy {
+    max_attempts: u32,
+    backoff: Backoff,
+    predicate: Option<RetryPredicate>,
+}
+
+impl std::fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("backoff", &self.backoff)
+            .field("predicate", &self.predicate.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a policy that runs the closure at most `max_attempts` times in total, retrying
+    /// immediately after every panic.
+    ///
+    /// A `max_attempts` of zero is treated as one, so the closure always runs at least once.
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            backoff: Backoff::None,
+            predicate: None,
+        }
+    }
+
+    /// Sets how long to wait between attempts.
+    #[must_use]
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Only retries panics for which `predicate` returns `true`. Any other panic ends the retries
+    /// immediately.
+    ///
+    /// For example, `.retry_if(|panic_data| panic_data.location.as_ref().is_some_and(|location|
+    /// location.file.contains("flaky_crate")))` only retries panics originating in `flaky_crate`.
+    #[must_use]
+    pub fn retry_if(
+        mut self,
+        predicate: impl Fn(&PanicData) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.predicate = Some(Box::new(predicate));
+        self
+    }
+}
+
+/// Invokes a closure, re-running it according to `policy` for as long as it panics.
+///
+/// Each attempt runs within a [`chillpill::catch`], so panics are captured (including their
+/// location and backtrace) and their output on `stderr` is suppressed.
+///
+/// The closure is re-run after a panic, so any state it captures may be observed in whatever
+/// condition the panic left it. By calling this function, you are asserting that this is fine.
+///
+/// # Errors
+///
+/// If no attempt succeeds (either because every attempt panicked, or because a panic did not match
+/// the policy's [`retry_if`](RetryPolicy::retry_if) predicate), returns the panic data from every
+/// attempt, in order.
+///
+/// # Panics
+///
+/// See [`chillpill::catch`].
+///
+/// [`chillpill::catch`]: crate::catch
+pub fn retry<F: FnMut() -> R + UnwindSafe, R>(
+    policy: &RetryPolicy,
+    mut f: F,
+) -> Result<R, Vec<PanicData>> {
+    let mut panics = Vec::new();
+
+    for attempt in 0..policy.max_attempts {
+        if attempt > 0 {
+            std::thread::sleep(policy.backoff.delay(attempt - 1));
+        }
+
+        // `F: UnwindSafe`, and the caller has explicitly opted into re-running `f` after it panics
+        let panic_data = match catch_inner(AssertUnwindSafe(&mut f), CaptureBacktrace::Default) {
+            Ok(value) => return Ok(value),
+            Err(panic_data) => panic_data,
+        };
+
+        let should_retry = policy
+            .predicate
+            .as_ref()
+            .is_none_or(|predicate| predicate(&panic_data));
+        panics.push(panic_data);
+
+        if !should_retry {
+            break;
+        }
+    }
+
+    Err(panics)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    /// This test ensures [`retry`] returns the first success after some panics.
+    #[test]
+    fn succeeds_after_panics() {
+        let attempts = AtomicU32::new(0);
+        let result = retry(&RetryPolicy::new(5), || {
+            let attempt = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+            assert!(attempt >= 3, "attempt {attempt} failed");
+
+            attempt
+        });
+
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    /// This test ensures [`retry`] returns the panic data from every attempt once it runs out of
+    /// attempts.
+    #[test]
+    fn returns_every_panic() {
+        let attempts = AtomicU32::new(0);
+        let panics = retry(&RetryPolicy::new(3), || {
+            let attempt = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+            panic!("attempt {attempt} failed");
+        })
+        .unwrap_err();
+
+        let messages: Vec<_> = panics
+            .iter()
+            .map(|panic_data| panic_data.payload_as_string().unwrap())
+            .collect();
+        assert_eq!(
+            messages,
+            ["attempt 1 failed", "attempt 2 failed", "attempt 3 failed"]
+        );
+    }
+
+    /// This test ensures [`retry`] stops retrying once a panic does not match the predicate.
+    #[test]
+    fn stops_on_predicate_mismatch() {
+        let policy = RetryPolicy::new(5)
+            .retry_if(|panic_data| panic_data.payload_as_string().as_deref() == Some("flaky"));
+        let attempts = AtomicU32::new(0);
+        let panics = retry(&policy, || {
+            let attempt = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+            assert!(attempt >= 2, "flaky");
+            panic!("broken");
+        })
+        .unwrap_err();
+
+        assert_eq!(panics.len(), 2);
+        assert_eq!(panics[1].payload_as_string().as_deref(), Some("broken"));
+    }
+
+    /// This test ensures exponential backoff grows by the given factor and is capped at the max.
+    #[test]
+    fn exponential_backoff_delays() {
+        let backoff = Backoff::Exponential {
+            initial: Duration::from_millis(10),
+            factor: 3,
+            max: Duration::from_millis(200),
+        };
+
+        let delays: Vec<_> = (0..5)
+            .map(|retry| backoff.delay(retry).as_millis())
+            .collect();
+        assert_eq!(delays, [10, 30, 90, 200, 200]);
+        assert_eq!(backoff.delay(u32::MAX), Duration::from_millis(200));
+    }
+}
@@ -0,0 +1,210 @@
+use std::{
+    borrow::Cow,
+    fmt::Write as _,
+    panic::UnwindSafe,
+    path::Path,
+    process::{ExitCode, Termination},
+    time::Duration,
+};
+
+use crate::{
+    PanicData, catch_inner, crash_report, thread_local_catch_stack::CaptureBacktrace, uncaught,
+};
+
+/// How long to wait for the uncaught panic reporter to deliver its reports before the process
+/// exits.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Runs a program's real `main` function, turning a panic into a user-friendly error report and an
+/// exit code.
+///
+/// This is shorthand for `MainConfig::new().run(f)` - see [`MainConfig`] for details and
+/// configuration options.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::process::ExitCode;
+///
+/// fn main() -> ExitCode {
+///     chillpill::main(|| real_main())
+/// }
+///
+/// fn real_main() {
+///     panic!("oh no");
+/// }
+/// ```
+///
+/// # Panics
+///
+/// See [`chillpill::catch`].
+///
+/// [`chillpill::catch`]: crate::catch
+pub fn main<F: FnOnce() -> T + UnwindSafe, T: Termination>(f: F) -> ExitCode {
+    MainConfig::new().run(f)
+}
+
+/// Configuration for running a program's real `main` function within a chillpill catch.
+///
+/// If `main` returns normally, its return value is turned into an [`ExitCode`] as usual (see
+/// [`Termination`]). If it panics, the raw panic message is suppressed, and a user-friendly error
+/// report is printed to `stderr` instead. The report includes the panic message and location, a
+/// request to report the bug, and the path of the crash report file if a [`CrashReporter`] is
+/// installed. The process then exits with a configurable exit code.
+///
+/// Either way, before returning, the uncaught panic reporter (if any) is given up to 5 seconds to
+/// deliver its reports (see [`flush_uncaught_reporter`]).
+///
+/// [`CrashReporter`]: crate::CrashReporter
+/// [`flush_uncaught_reporter`]: crate::flush_uncaught_reporter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MainConfig {
+    exit_code: u8,
+    bug_report_message: Cow<'static, str>,
+}
+
+impl MainConfig {
+    /// Creates a configuration that exits with code 101 (like an uncaught panic would) and asks the
+    /// user to file a bug report.
+    pub fn new() -> Self {
+        Self {
+            exit_code: 101,
+            bug_report_message: Cow::Borrowed(
+                "This is a bug. Please file a bug report, including the information above.",
+            ),
+        }
+    }
+
+    /// Sets the exit code used when `main` panics.
+    #[must_use]
+    pub fn exit_code(mut self, exit_code: u8) -> Self {
+        self.exit_code = exit_code;
+        self
+    }
+
+    /// Sets the message printed at the end of the error report, usually asking the user to file a
+    /// bug report (and where).
+    #[must_use]
+    pub fn bug_report_message(mut self, bug_report_message: impl Into<Cow<'static, str>>) -> Self {
+        self.bug_report_message = bug_report_message.into();
+        self
+    }
+
+    /// Runs `f` as the program's real `main` function, returning the [`ExitCode`] the process
+    /// should exit with.
+    ///
+    /// # Panics
+    ///
+    /// See [`chillpill::catch`].
+    ///
+    /// [`chillpill::catch`]: crate::catch
+    pub fn run<F: FnOnce() -> T + UnwindSafe, T: Termination>(&self, f: F) -> ExitCode {
+        let exit_code = match catch_inner(f, CaptureBacktrace::Always) {
+            Ok(value) => value.report(),
+            Err(panic_data) => {
+                let crash_report_path =
+                    crash_report::report_caught_panic(&panic_data, "panic in `main`");
+                let report = self.render_report(&panic_data, crash_report_path.as_deref());
+
+                // Print through `eprint!` rather than writing to stderr directly, so that the
+                // test harness can capture the report
+                eprint!("{report}");
+
+                ExitCode::from(self.exit_code)
+            }
+        };
+
+        // The process is about to exit, so this is the last chance to deliver reports of panics
+        // on other threads
+        uncaught::flush_uncaught_reporter(FLUSH_TIMEOUT);
+
+        exit_code
+    }
+
+    fn render_report(&self, panic_data: &PanicData, crash_report_path: Option<&Path>) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "error: the program encountered an unexpected error");
+        let _ = writeln!(out);
+        let _ = writeln!(
+            out,
+            "  message: {}",
+            panic_data
+                .payload_as_string()
+                .unwrap_or(Cow::Borrowed("unknown"))
+        );
+        if let Some(location) = &panic_data.location {
+            let _ = writeln!(out, "  location: {location}");
+        }
+        if let Some(crash_report_path) = crash_report_path {
+            let _ = writeln!(out, "  crash report: {}", crash_report_path.display());
+        }
+        let _ = writeln!(out);
+        let _ = writeln!(out, "{}", self.bug_report_message);
+
+        out
+    }
+}
+
+impl Default for MainConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::backtrace::Backtrace;
+
+    use super::*;
+
+    use crate::{PanicLocation, panic_data::PanicContext};
+
+    /// This test ensures a `main` that returns normally produces the exit code it reports.
+    #[test]
+    fn returns_exit_code_without_panic() {
+        assert_eq!(
+            MainConfig::new().run(|| ExitCode::from(7)),
+            ExitCode::from(7)
+        );
+        assert_eq!(MainConfig::new().run(|| ()), ExitCode::SUCCESS);
+    }
+
+    /// This test ensures a `main` that panics produces the configured exit code.
+    #[test]
+    fn returns_configured_exit_code_on_panic() {
+        let exit_code = MainConfig::new().exit_code(3).run(|| {
+            panic!("oh no");
+        });
+
+        assert_eq!(exit_code, ExitCode::from(3));
+    }
+
+    /// This test ensures the error report includes the panic message, location, crash report path,
+    /// and bug report message.
+    #[test]
+    fn renders_report() {
+        let panic_data = PanicData {
+            payload: Box::new("oh no"),
+            location: Some(PanicLocation {
+                file: String::from("src/main.rs"),
+                line: 8,
+                col: 5,
+            }),
+            backtrace: Backtrace::disabled(),
+            context: PanicContext::default(),
+        };
+        let config = MainConfig::new().bug_report_message("Please report this at example.com.");
+
+        let report = config.render_report(&panic_data, Some(Path::new("/tmp/crash.txt")));
+
+        assert_eq!(
+            report,
+            "error: the program encountered an unexpected error\n\
+             \n  message: oh no\
+             \n  location: src/main.rs:8:5\
+             \n  crash report: /tmp/crash.txt\
+             \n\
+             \nPlease report this at example.com.\n"
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Generation of Sentry event payloads (see [`PanicReport::to_sentry_event`]).
+
+use std::{
+    backtrace::BacktraceStatus,
+    fmt::Write as _,
+    hash::{BuildHasher, RandomState},
+    sync::atomic::{AtomicU64, Ordering},
+    thread::ThreadId,
+};
+
+use crate::{
+    PanicReport,
+    crash_report::unix_time,
+    fingerprint::{is_in_crate_path, parse_frames_below_panic},
+    json,
+};
+
+/// Returns a new random event ID: 32 lowercase hexadecimal digits.
+pub(crate) fn new_event_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    // `RandomState` is randomly seeded, and the counter keeps two IDs from colliding even if the
+    // seeds do
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let state = RandomState::new();
+    let high = state.hash_one((count, 0_u8));
+    let low = state.hash_one((count, 1_u8));
+
+    format!("{high:016x}{low:016x}")
+}
+
+/// Appends `report` to `out` as a Sentry event with the given ID (see
+/// [`PanicReport::to_sentry_event`]).
+pub(crate) fn write_event(out: &mut String, report: &PanicReport, event_id: &str) {
+    let time = unix_time(report.timestamp);
+
+    out.push_str("{\"event_id\":");
+    json::write_string(out, event_id);
+    let _ = write!(
+        out,
+        ",\"timestamp\":{}.{:03},\"platform\":\"native\",\"level\":\"error\"",
+        time.as_secs(),
+        time.subsec_millis()
+    );
+
+    out.push_str(",\"exception\":{\"values\":[{\"type\":\"panic\",\"value\":");
+    json::write_string(out, report.message.as_deref().unwrap_or("Box<dyn Any>"));
+    out.push_str(",\"mechanism\":{\"type\":\"panic\"");
+    if let Some(payload_type) = report.payload_type {
+        out.push_str(",\"data\":{\"payload_type\":");
+        json::write_string(out, payload_type);
+        out.push('}');
+    }
+    out.push_str("},\"thread_id\":");
+    write_thread_id(out, report.thread.id);
+    write_stacktrace(out, report);
+    out.push_str("}]}");
+
+    out.push_str(",\"threads\":{\"values\":[{\"id\":");
+    write_thread_id(out, report.thread.id);
+    out.push_str(",\"name\":");
+    json::write_optional_string(out, report.thread.name.as_deref());
+    out.push_str(",\"crashed\":true,\"current\":true}]}");
+
+    out.push_str(",\"tags\":");
+    json::write_string_map(out, &report.tags);
+    out.push_str(",\"extra\":");
+    json::write_string_map(out, &report.context);
+    let _ = write!(out, ",\"fingerprint\":[\"{}\"]}}", report.fingerprint());
+}
+
+/// Appends a thread ID to `out`, as a number if possible.
+fn write_thread_id(out: &mut String, id: ThreadId) {
+    // `ThreadId::as_u64` is unstable, so extract the number from the `Debug` output instead
+    let debug = format!("{id:?}");
+    match debug
+        .strip_prefix("ThreadId(")
+        .and_then(|rest| rest.strip_suffix(')'))
+        .and_then(|number| number.parse::<u64>().ok())
+    {
+        Some(number) => {
+            let _ = write!(out, "{number}");
+        }
+        None => json::write_string(out, &debug),
+    }
+}
+
+/// Appends the `stacktrace` member of the exception to `out` (including the leading comma), or
+/// nothing if the report has neither a backtrace nor a location.
+///
+/// Sentry expects frames ordered from the outermost caller to the innermost frame.
+fn write_stacktrace(out: &mut String, report: &PanicReport) {
+    if report.backtrace.status() == BacktraceStatus::Captured {
+        let backtrace = report.backtrace.to_string();
+        out.push_str(",\"stacktrace\":");
+        write_backtrace_frames(out, &backtrace);
+    } else if let Some(location) = &report.location {
+        out.push_str(",\"stacktrace\":{\"frames\":[{\"filename\":");
+        json::write_string(out, &location.file);
+        let _ = write!(
+            out,
+            ",\"lineno\":{},\"colno\":{},\"in_app\":{}}}]}}",
+            location.line,
+            location.col,
+            is_in_crate_path(&location.file)
+        );
+    }
+}
+
+/// Appends the frames of a backtrace (in its [`Display`](std::fmt::Display) output) to `out` as a
+/// Sentry stack trace, below the panic machinery.
+fn write_backtrace_frames(out: &mut String, backtrace: &str) {
+    out.push_str("{\"frames\":[");
+    for (index, (symbol, location)) in parse_frames_below_panic(backtrace)
+        .into_iter()
+        .rev()
+        .enumerate()
+    {
+        if index > 0 {
+            out.push(',');
+        }
+        out.push_str("{\"function\":");
+        json::write_string(out, symbol);
+        if let Some(location) = location {
+            let (file, line_col) = split_location(location);
+            out.push_str(",\"filename\":");
+            json::write_string(out, file);
+            if let Some((line, col)) = line_col {
+                let _ = write!(out, ",\"lineno\":{line},\"colno\":{col}");
+            }
+            let _ = write!(out, ",\"in_app\":{}", is_in_crate_path(file));
+        }
+        out.push('}');
+    }
+    out.push_str("]}");
+}
+
+/// Splits a source location like `"src/main.rs:3:5"` into its file, and its line and column if
+/// present.
+fn split_location(location: &str) -> (&str, Option<(u32, u32)>) {
+    let parsed = location.rsplit_once(':').and_then(|(rest, col)| {
+        let (file, line) = rest.rsplit_once(':')?;
+        Some((file, (line.parse().ok()?, col.parse().ok()?)))
+    });
+
+    match parsed {
+        Some((file, line_col)) => (file, Some(line_col)),
+        None => (location, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// This test ensures event IDs are 32 hexadecimal digits, and differ between events.
+    #[test]
+    fn event_ids() {
+        let first = new_event_id();
+        let second = new_event_id();
+
+        assert_eq!(first.len(), 32);
+        assert!(first.bytes().all(|byte| byte.is_ascii_hexdigit()));
+        assert_ne!(first, second);
+    }
+
+    /// This test ensures backtrace frames are ordered from the outermost caller, below the panic
+    /// machinery, with their locations split into parts.
+    #[test]
+    fn backtrace_frames() {
+        let backtrace = "   0: app::hook
+             at ./src/hook.rs:3:49
+   1: __rustc::rust_begin_unwind
+             at /rustc/0123/library/std/src/panicking.rs:689:5
+   2: app::inner
+             at ./src/main.rs:1:48
+   3: __rust_try
+   4: std::rt::lang_start
+             at /rustc/0123/library/std/src/rt.rs:206:5
+";
+        let mut out = String::new();
+        write_backtrace_frames(&mut out, backtrace);
+
+        assert_eq!(
+            out,
+            concat!(
+                r#"{"frames":["#,
+                r#"{"function":"std::rt::lang_start","filename":"/rustc/0123/library/std/src/rt.rs","lineno":206,"colno":5,"in_app":false},"#,
+                r#"{"function":"__rust_try"},"#,
+                r#"{"function":"app::inner","filename":"./src/main.rs","lineno":1,"colno":48,"in_app":true}"#,
+                r#"]}"#
+            )
+        );
+    }
+
+    /// This test ensures locations without a line and column are kept whole.
+    #[test]
+    fn splits_locations() {
+        assert_eq!(
+            split_location("C:\\src\\main.rs:3:5"),
+            ("C:\\src\\main.rs", Some((3, 5)))
+        );
+        assert_eq!(split_location("src/main.rs"), ("src/main.rs", None));
+    }
+}
@@ -0,0 +1,267 @@
+use std::{
+    collections::HashMap,
+    panic::{Location, PanicHookInfo},
+    sync::{
+        LazyLock, Mutex, PoisonError,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use crate::PanicLocation;
+
+/// Whether to record how long each `catch` call ran before catching a panic. Off until enabled, so
+/// that `catch` calls don't need to read the clock.
+static CATCH_DURATIONS_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The number of panics caught by `chillpill::catch`, seen by the chillpill panic hook.
+static CAUGHT: AtomicU64 = AtomicU64::new(0);
+
+/// The number of panics not caught by `chillpill::catch`, seen by the chillpill panic hook.
+static UNCAUGHT: AtomicU64 = AtomicU64::new(0);
+
+/// The breakdowns of the counts, which need a lock (unlike the totals). They are best-effort, and
+/// may briefly lag behind the totals.
+static BREAKDOWNS: LazyLock<Mutex<Breakdowns>> = LazyLock::new(Mutex::default);
+
+/// Values by panic location, keyed by file and then by line and column, so that an existing entry
+/// can be looked up without allocating.
+type ByLocation<T> = HashMap<String, HashMap<(u32, u32), T>>;
+
+#[derive(Debug, Default)]
+struct Breakdowns {
+    by_location: ByLocation<PanicCounts>,
+    by_named_thread: HashMap<String, PanicCounts>,
+    by_unnamed_thread: PanicCounts,
+    catch_durations: ByLocation<DurationHistogram>,
+}
+
+/// Returns the value for `key`, inserting a default value if there is none. Unlike
+/// [`HashMap::entry`], this only allocates a [`String`] for the key when inserting.
+fn str_entry<'a, T: Default>(map: &'a mut HashMap<String, T>, key: &str) -> Option<&'a mut T> {
+    if !map.contains_key(key) {
+        map.insert(key.to_string(), T::default());
+    }
+
+    map.get_mut(key)
+}
+
+/// Returns the value for `location`, inserting a default value if there is none.
+fn location_entry<'a, T: Default>(
+    map: &'a mut ByLocation<T>,
+    location: &Location<'_>,
+) -> Option<&'a mut T> {
+    str_entry(map, location.file()).map(|by_position| {
+        by_position
+            .entry((location.line(), location.column()))
+            .or_default()
+    })
+}
+
+/// Flattens values by location back into a map keyed by [`PanicLocation`].
+fn by_panic_location<T: Clone>(map: &ByLocation<T>) -> HashMap<PanicLocation, T> {
+    map.iter()
+        .flat_map(|(file, by_position)| {
+            by_position.iter().map(|(&(line, col), value)| {
+                let location = PanicLocation {
+                    file: file.clone(),
+                    line,
+                    col,
+                };
+                (location, value.clone())
+            })
+        })
+        .collect()
+}
+
+/// The upper bounds of the [`DurationHistogram`] buckets, in seconds.
+pub(crate) const DURATION_BUCKETS: [f64; 7] = [0.0001, 0.001, 0.01, 0.1, 1.0, 10.0, 100.0];
+
+/// A histogram of durations, with the buckets in [`DURATION_BUCKETS`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct DurationHistogram {
+    /// The number of durations in each bucket (not cumulative), with one more bucket for durations
+    /// above the last bound.
+    pub buckets: [u64; DURATION_BUCKETS.len() + 1],
+
+    /// The sum of all durations, in seconds.
+    pub sum: f64,
+}
+
+impl DurationHistogram {
+    pub(crate) fn record(&mut self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        let bucket = DURATION_BUCKETS
+            .iter()
+            .position(|&bound| seconds <= bound)
+            .unwrap_or(DURATION_BUCKETS.len());
+        self.buckets[bucket] += 1;
+        self.sum += seconds;
+    }
+
+    /// Merges the durations of `other` into this histogram.
+    pub(crate) fn merge(&mut self, other: &Self) {
+        for (bucket, other_bucket) in self.buckets.iter_mut().zip(other.buckets) {
+            *bucket += other_bucket;
+        }
+        self.sum += other.sum;
+    }
+}
+
+/// Counts of panics, split by whether they were caught by [`chillpill::catch`].
+///
+/// [`chillpill::catch`]: crate::catch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct PanicCounts {
+    /// The number of panics caught by a [`chillpill::catch`].
+    ///
+    /// [`chillpill::catch`]: crate::catch
+    pub caught: u64,
+
+    /// The number of panics not caught by any [`chillpill::catch`].
+    ///
+    /// [`chillpill::catch`]: crate::catch
+    pub uncaught: u64,
+}
+
+impl PanicCounts {
+    /// Returns the total number of panics, caught or not.
+    pub fn total(&self) -> u64 {
+        self.caught + self.uncaught
+    }
+
+    fn record(&mut self, caught: bool) {
+        if caught {
+            self.caught += 1;
+        } else {
+            self.uncaught += 1;
+        }
+    }
+}
+
+/// Statistics about every panic in the process seen by the chillpill panic hook.
+///
+/// See [`chillpill::stats`].
+///
+/// [`chillpill::stats`]: crate::stats()
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PanicStats {
+    /// The counts of all panics.
+    pub total: PanicCounts,
+
+    /// The counts of panics at each location. Panics without a location are not included.
+    pub by_location: HashMap<PanicLocation, PanicCounts>,
+
+    /// The counts of panics on threads with each name, with unnamed threads under [`None`].
+    pub by_thread: HashMap<Option<String>, PanicCounts>,
+}
+
+/// Returns statistics about every panic in the process seen by the chillpill panic hook, since it
+/// was installed (or since the last [`reset_stats`]).
+///
+/// Panics are counted on any thread, both those caught by a [`chillpill::catch`] and those that are
+/// not. Panics that occur before the chillpill panic hook is installed (by the first call to
+/// `chillpill::catch`, or to a function like [`set_uncaught_handler`]) are not counted.
+///
+/// Counting is cheap, so this is suitable for exposing panic rates (for example, on a health
+/// endpoint) without a dedicated panic reporter.
+///
+/// The totals are exact, while the breakdowns by location and thread are best-effort: a panic that
+/// is being counted on another thread may already be included in the totals, but not yet in the
+/// breakdowns.
+///
+/// # Example
+///
+/// ```
+/// chillpill::catch(|| panic!("oh no")).unwrap_err();
+///
+/// let stats = chillpill::stats();
+/// assert!(stats.total.caught >= 1);
+/// ```
+///
+/// [`chillpill::catch`]: crate::catch
+/// [`set_uncaught_handler`]: crate::set_uncaught_handler
+pub fn stats() -> PanicStats {
+    let breakdowns = BREAKDOWNS.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let mut by_thread: HashMap<_, _> = breakdowns
+        .by_named_thread
+        .iter()
+        .map(|(name, counts)| (Some(name.clone()), *counts))
+        .collect();
+    if breakdowns.by_unnamed_thread.total() > 0 {
+        by_thread.insert(None, breakdowns.by_unnamed_thread);
+    }
+
+    PanicStats {
+        total: PanicCounts {
+            caught: CAUGHT.load(Ordering::Relaxed),
+            uncaught: UNCAUGHT.load(Ordering::Relaxed),
+        },
+        by_location: by_panic_location(&breakdowns.by_location),
+        by_thread,
+    }
+}
+
+/// Resets the statistics returned by [`chillpill::stats`](crate::stats()) to zero.
+pub fn reset_stats() {
+    let mut breakdowns = BREAKDOWNS.lock().unwrap_or_else(PoisonError::into_inner);
+    CAUGHT.store(0, Ordering::Relaxed);
+    UNCAUGHT.store(0, Ordering::Relaxed);
+    *breakdowns = Breakdowns::default();
+}
+
+/// Starts recording how long each `catch` call runs before catching a panic.
+pub(crate) fn enable_catch_durations() {
+    CATCH_DURATIONS_ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Returns whether catch durations are being recorded (see [`enable_catch_durations`]).
+pub(crate) fn catch_durations_enabled() -> bool {
+    CATCH_DURATIONS_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Returns the histograms of how long each `catch` call ran before catching a panic, by panic
+/// location.
+pub(crate) fn catch_durations() -> HashMap<PanicLocation, DurationHistogram> {
+    by_panic_location(
+        &BREAKDOWNS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .catch_durations,
+    )
+}
+
+/// Counts a panic. Called from the chillpill panic hook.
+///
+/// `caught_after` is how long the `catch` call that will catch the panic ran before it panicked, or
+/// [`None`] if the panic will not be caught.
+pub(crate) fn record_panic(info: &PanicHookInfo<'_>, caught: bool, caught_after: Option<Duration>) {
+    if caught {
+        CAUGHT.fetch_add(1, Ordering::Relaxed);
+    } else {
+        UNCAUGHT.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let thread = std::thread::current();
+
+    let mut breakdowns = BREAKDOWNS.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(location) = info.location() {
+        if let Some(counts) = location_entry(&mut breakdowns.by_location, location) {
+            counts.record(caught);
+        }
+        if let Some(caught_after) = caught_after {
+            if let Some(histogram) = location_entry(&mut breakdowns.catch_durations, location) {
+                histogram.record(caught_after);
+            }
+        }
+    }
+    match thread.name() {
+        Some(name) => {
+            if let Some(counts) = str_entry(&mut breakdowns.by_named_thread, name) {
+                counts.record(caught);
+            }
+        }
+        None => breakdowns.by_unnamed_thread.record(caught),
+    }
+}
@@ -0,0 +1,170 @@
+use std::fmt::Display;
+
+/// A panic payload carrying an error code and key-value fields alongside its message.
+///
+/// Plain string payloads are hard to route: grouping or alerting on them means parsing text. A
+/// structured payload (usually created via [`panic_with!`](crate::panic_with)) keeps the parts
+/// separate, and they are available from a caught panic through [`PanicData::code`] and
+/// [`PanicData::fields`]. Its message is still returned by [`PanicData::payload_as_string`].
+///
+/// Note that the standard library's default panic hook only prints messages of `&str` and `String`
+/// payloads, so it prints this payload as `Box<dyn Any>`.
+///
+/// [`PanicData::code`]: crate::PanicData::code
+/// [`PanicData::fields`]: crate::PanicData::fields
+/// [`PanicData::payload_as_string`]: crate::PanicData::payload_as_string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructuredPayload {
+    code: Option<String>,
+    message: String,
+    fields: Vec<(String, String)>,
+}
+
+impl StructuredPayload {
+    /// Creates a structured payload with the given message, and no code or fields.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            code: None,
+            message: message.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Sets the error code, replacing any previous code.
+    #[must_use]
+    pub fn with_code(mut self, code: impl Display) -> Self {
+        self.code = Some(code.to_string());
+        self
+    }
+
+    /// Adds a key-value field. Fields keep the order they were added in.
+    #[must_use]
+    pub fn with_field(mut self, key: impl Into<String>, value: impl Display) -> Self {
+        self.fields.push((key.into(), value.to_string()));
+        self
+    }
+
+    /// Returns the error code, if any.
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    /// Returns the message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the key-value fields, in the order they were added.
+    pub fn fields(&self) -> &[(String, String)] {
+        &self.fields
+    }
+
+    /// Returns the value of the first field with the given key, if any.
+    pub fn field(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field_key, _)| field_key == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl Display for StructuredPayload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(code) = &self.code {
+            write!(f, "[{code}] ")?;
+        }
+        write!(f, "{}", self.message)?;
+        for (key, value) in &self.fields {
+            write!(f, " {key}={value}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Panics with a [`StructuredPayload`] carrying an error code, key-value fields, and a message.
+///
+/// Fields come first, as `key = value` pairs (where each value implements [`Display`]), followed by
+/// a semicolon and the same arguments [`panic!`] takes for its message. The `code` key is special:
+/// it sets the payload's error code rather than adding a field.
+///
+/// # Example
+///
+/// ```
+/// let user = 42;
+/// let panic_data = chillpill::catch(|| {
+///     chillpill::panic_with!(code = "E1234", user = user; "no profile for user {user}");
+/// })
+/// .unwrap_err();
+///
+/// assert_eq!(panic_data.code(), Some("E1234"));
+/// assert_eq!(panic_data.fields(), [(String::from("user"), String::from("42"))]);
+/// assert_eq!(panic_data.payload_as_string().as_deref(), Some("no profile for user 42"));
+/// ```
+#[macro_export]
+macro_rules! panic_with {
+    ($($key:ident = $value:expr),+ $(,)?; $($arg:tt)+) => {{
+        let payload = $crate::StructuredPayload::new(::std::format!($($arg)+));
+        $(let payload = $crate::__panic_with_field!(payload, $key, $value);)+
+        ::std::panic::panic_any(payload)
+    }};
+    ($($arg:tt)+) => {
+        ::std::panic::panic_any($crate::StructuredPayload::new(::std::format!($($arg)+)))
+    };
+}
+
+/// Implementation detail of [`panic_with!`] - adds a field (or the code) to `$payload`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __panic_with_field {
+    ($payload:ident, code, $value:expr) => {
+        $payload.with_code($value)
+    };
+    ($payload:ident, $key:ident, $value:expr) => {
+        $payload.with_field(::core::stringify!($key), $value)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::catch;
+
+    /// This test ensures [`panic_with!`] sets the code, fields (in order), and formatted message.
+    #[test]
+    fn panic_with_builds_payload() {
+        let user = 7;
+        let panic_data = catch(|| {
+            crate::panic_with!(code = "E1", user = user, retries = 3,; "user {} failed", user);
+        })
+        .unwrap_err();
+
+        let payload = panic_data
+            .payload
+            .downcast_ref::<StructuredPayload>()
+            .unwrap();
+        assert_eq!(
+            payload,
+            &StructuredPayload::new("user 7 failed")
+                .with_code("E1")
+                .with_field("user", 7)
+                .with_field("retries", 3)
+        );
+        assert_eq!(payload.field("retries"), Some("3"));
+        assert_eq!(payload.to_string(), "[E1] user 7 failed user=7 retries=3");
+    }
+
+    /// This test ensures [`panic_with!`] works without any fields.
+    #[test]
+    fn panic_with_message_only() {
+        let panic_data = catch(|| crate::panic_with!("just {}", "a message")).unwrap_err();
+
+        assert_eq!(panic_data.code(), None);
+        assert!(panic_data.fields().is_empty());
+        assert_eq!(
+            panic_data.payload_as_string().as_deref(),
+            Some("just a message")
+        );
+    }
+}
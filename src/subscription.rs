@@ -0,0 +1,126 @@
+use std::{
+    backtrace::Backtrace,
+    panic::PanicHookInfo,
+    sync::{
+        Arc, Mutex, PoisonError,
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+    },
+};
+
+use crate::{PanicReport, panic_hook};
+
+/// The capacity of the channel returned by [`subscribe`].
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// The senders of every subscriber's channel.
+static SUBSCRIBERS: Mutex<Vec<SyncSender<PanicEvent>>> = Mutex::new(Vec::new());
+
+/// A panic, as seen by the chillpill panic hook, sent to every subscriber (see [`subscribe`]).
+#[derive(Debug, Clone)]
+pub struct PanicEvent {
+    /// A report of the panic.
+    pub report: PanicReport,
+
+    /// Whether a [`chillpill::catch`] will catch the panic.
+    ///
+    /// [`chillpill::catch`]: crate::catch
+    pub caught: bool,
+
+    /// The label of the [`Catcher`] that will catch the panic (see [`Catcher::label`]), if it has
+    /// one.
+    ///
+    /// [`Catcher`]: crate::Catcher
+    /// [`Catcher::label`]: crate::Catcher::label
+    pub catch_label: Option<String>,
+}
+
+/// Subscribes to every panic in the process, returning a channel that receives an event for each
+/// one.
+///
+/// Events are sent for panics on any thread, whether they are caught by [`chillpill::catch`], by
+/// [`std::panic::catch_unwind`], or not at all. Any number of independent subscribers (for
+/// metrics, logging, or test assertions) may coexist, and subscribing does not replace the global
+/// panic hook. A subscriber is removed once its receiver is dropped.
+///
+/// Events are sent without blocking the panicking thread: if a subscriber falls behind and its
+/// channel fills up (after 1024 events, see [`subscribe_with_capacity`]), further events are
+/// dropped for that subscriber until it catches up.
+///
+/// Panics that occur before the chillpill panic hook is installed are not sent, but subscribing
+/// installs it.
+///
+/// # Example
+///
+/// ```
+/// let events = chillpill::subscribe();
+///
+/// let catcher = chillpill::Catcher::new().label("request handler");
+/// catcher.catch(|| panic!("oh no")).unwrap_err();
+///
+/// let event = events.recv().unwrap();
+/// assert_eq!(event.report.message.as_deref(), Some("oh no"));
+/// assert!(event.caught);
+/// assert_eq!(event.catch_label.as_deref(), Some("request handler"));
+/// ```
+///
+/// # Panics
+///
+/// Panics if the chillpill panic hook is not yet installed and this thread is currently unwinding
+/// from a panic.
+///
+/// [`chillpill::catch`]: crate::catch
+pub fn subscribe() -> Receiver<PanicEvent> {
+    subscribe_with_capacity(DEFAULT_CAPACITY)
+}
+
+/// Like [`subscribe`], but with a channel that holds at most `capacity` unreceived events.
+///
+/// A capacity of zero is treated as one, since events are never waited on to be received.
+///
+/// # Panics
+///
+/// Panics if the chillpill panic hook is not yet installed and this thread is currently unwinding
+/// from a panic.
+pub fn subscribe_with_capacity(capacity: usize) -> Receiver<PanicEvent> {
+    if let Err(()) = panic_hook::install_if_not_installed() {
+        panic!("the chillpill panic hook must not be installed from a panicking thread");
+    }
+
+    let (tx, rx) = mpsc::sync_channel(capacity.max(1));
+    SUBSCRIBERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(tx);
+
+    rx
+}
+
+/// Sends an event for a panic to every subscriber. Called from the chillpill panic hook.
+///
+/// `catch_label` is the label of the catch that will catch the panic, if it will be caught.
+/// `backtrace` returns the backtrace shared by everything in the hook that reports the panic, and is
+/// only called if there are subscribers.
+pub(crate) fn notify_subscribers(
+    info: &PanicHookInfo<'_>,
+    caught: bool,
+    catch_label: Option<&str>,
+    backtrace: impl FnOnce() -> Arc<Backtrace>,
+) {
+    let mut subscribers = SUBSCRIBERS.lock().unwrap_or_else(PoisonError::into_inner);
+
+    // Avoid building the event (and capturing a backtrace) without any subscribers
+    if subscribers.is_empty() {
+        return;
+    }
+
+    let event = PanicEvent {
+        report: PanicReport::from_hook_info(info, backtrace()),
+        caught,
+        catch_label: catch_label.map(String::from),
+    };
+
+    subscribers.retain(|tx| match tx.try_send(event.clone()) {
+        Ok(()) | Err(TrySendError::Full(_)) => true,
+        Err(TrySendError::Disconnected(_)) => false,
+    });
+}
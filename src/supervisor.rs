@@ -0,0 +1,543 @@
+use std::{
+    collections::VecDeque,
+    io,
+    ops::Range,
+    panic::AssertUnwindSafe,
+    sync::{Arc, mpsc},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    CancellationToken, PanicData, catch_inner, thread_local_catch_stack::CaptureBacktrace,
+};
+
+type WorkerFn = Arc<dyn Fn(&CancellationToken) + Send + Sync>;
+type WorkerPanicCallback = Box<dyn Fn(&WorkerPanic) + Send + Sync>;
+type EscalationCallback = Box<dyn Fn(&[WorkerPanic]) + Send + Sync>;
+
+/// Which workers a [`Supervisor`] restarts when one of them panics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RestartStrategy {
+    /// Restart only the worker that panicked.
+    OneForOne,
+
+    /// Stop all other running workers, then restart all of them along with the worker that
+    /// panicked.
+    OneForAll,
+
+    /// Stop all running workers added after the worker that panicked, then restart all of them
+    /// along with the worker that panicked.
+    RestForOne,
+}
+
+/// A panic in one of a [`Supervisor`]'s workers.
+#[derive(Debug)]
+pub struct WorkerPanic {
+    /// The name of the worker that panicked.
+    pub worker: String,
+
+    /// The panic data of the worker's panic.
+    pub panic_data: PanicData,
+}
+
+/// How a call to [`Supervisor::run`] ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SupervisorExit {
+    /// Every worker returned without panicking (for example, after being cancelled).
+    Finished,
+
+    /// Workers panicked more often than the restart intensity allows, so every worker was stopped
+    /// and the escalation callback was invoked.
+    Escalated,
+}
+
+/// A supervisor for long-lived worker threads, which restarts workers that panic.
+///
+/// Each worker runs on its own named thread, within a [`chillpill::catch`], so a worker's panic is
+/// captured and its output on `stderr` is suppressed. Panics are reported to the
+/// [`on_panic`](Supervisor::on_panic) callback instead, and the worker (and possibly some of its
+/// siblings, see [`RestartStrategy`]) is restarted. If workers panic more than a configured number
+/// of times within a configured period (see [`intensity`](Supervisor::intensity)), the supervisor
+/// gives up: it stops every worker and invokes the [`on_escalation`](Supervisor::on_escalation)
+/// callback with the panics that exceeded the limit.
+///
+/// Threads cannot be stopped from the outside, so workers are stopped cooperatively. Each worker is
+/// given a [`CancellationToken`] it must check regularly, returning once it is cancelled. A worker
+/// that returns without panicking is considered finished, and is not restarted.
+///
+/// # Example
+///
+/// ```
+/// use chillpill::{RestartStrategy, Supervisor, SupervisorExit};
+///
+/// let supervisor = Supervisor::new()
+///     .strategy(RestartStrategy::OneForOne)
+///     .on_panic(|worker_panic| eprintln!("worker {} panicked", worker_panic.worker))
+///     .worker("ticker", |token| {
+///         while !token.is_cancelled() {
+///             # token.cancel();
+///             // Do some work
+///         }
+///     });
+///
+/// assert_eq!(supervisor.run().unwrap(), SupervisorExit::Finished);
+/// ```
+///
+/// [`chillpill::catch`]: crate::catch
+pub struct Supervisor {
+    workers: Vec<WorkerSpec>,
+    strategy: RestartStrategy,
+    max_restarts: usize,
+    period: Duration,
+    on_panic: Option<WorkerPanicCallback>,
+    on_escalation: Option<EscalationCallback>,
+    token: CancellationToken,
+}
+
+impl std::fmt::Debug for Supervisor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Supervisor")
+            .field("workers", &self.workers)
+            .field("strategy", &self.strategy)
+            .field("max_restarts", &self.max_restarts)
+            .field("period", &self.period)
+            .field("on_panic", &self.on_panic.as_ref().map(|_| ".."))
+            .field("on_escalation", &self.on_escalation.as_ref().map(|_| ".."))
+            .field("token", &self.token)
+            .finish()
+    }
+}
+
+struct WorkerSpec {
+    name: String,
+    f: WorkerFn,
+}
+
+impl std::fmt::Debug for WorkerSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WorkerSpec")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A worker thread started by [`Supervisor::run`].
+struct RunningWorker {
+    generation: u64,
+    token: CancellationToken,
+    handle: JoinHandle<()>,
+}
+
+impl RunningWorker {
+    /// Asks the worker to stop, and waits until it does.
+    fn stop(self) {
+        self.token.cancel();
+
+        // The worker's panics are caught within the thread, so joining cannot fail
+        let _ = self.handle.join();
+    }
+}
+
+/// A message from a worker thread to the supervisor, sent when the worker returns or panics.
+struct WorkerExit {
+    index: usize,
+    generation: u64,
+
+    /// The result of the worker's catch, or the payload of a fatal panic that passed through it.
+    result: std::thread::Result<crate::Result<()>>,
+}
+
+impl Supervisor {
+    /// Creates a supervisor with no workers, using [`RestartStrategy::OneForOne`] and allowing at
+    /// most 3 restarts within 5 seconds.
+    pub fn new() -> Self {
+        Self {
+            workers: Vec::new(),
+            strategy: RestartStrategy::OneForOne,
+            max_restarts: 3,
+            period: Duration::from_secs(5),
+            on_panic: None,
+            on_escalation: None,
+            token: CancellationToken::new(),
+        }
+    }
+
+    /// Adds a worker, which runs `f` on a thread named `name`.
+    ///
+    /// `f` is called again every time the worker is restarted, so any state it captures may be
+    /// observed in whatever condition a panic left it. By adding a worker, you are asserting that
+    /// this is fine.
+    #[must_use]
+    pub fn worker(
+        mut self,
+        name: impl Into<String>,
+        f: impl Fn(&CancellationToken) + Send + Sync + 'static,
+    ) -> Self {
+        self.workers.push(WorkerSpec {
+            name: name.into(),
+            f: Arc::new(f),
+        });
+        self
+    }
+
+    /// Sets which workers are restarted when one of them panics.
+    #[must_use]
+    pub fn strategy(mut self, strategy: RestartStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Sets the restart intensity: the supervisor escalates once workers panic more than
+    /// `max_restarts` times within any `period`.
+    #[must_use]
+    pub fn intensity(mut self, max_restarts: usize, period: Duration) -> Self {
+        self.max_restarts = max_restarts;
+        self.period = period;
+        self
+    }
+
+    /// Sets a callback invoked (on the thread calling [`Supervisor::run`]) for every worker panic,
+    /// before the worker is restarted. This is the place to log worker panics.
+    #[must_use]
+    pub fn on_panic(mut self, on_panic: impl Fn(&WorkerPanic) + Send + Sync + 'static) -> Self {
+        self.on_panic = Some(Box::new(on_panic));
+        self
+    }
+
+    /// Sets a callback invoked (on the thread calling [`Supervisor::run`]) when the restart
+    /// intensity is exceeded, after every worker has been stopped. It receives every worker panic
+    /// within the most recent restart intensity period, in order.
+    #[must_use]
+    pub fn on_escalation(
+        mut self,
+        on_escalation: impl Fn(&[WorkerPanic]) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_escalation = Some(Box::new(on_escalation));
+        self
+    }
+
+    /// Returns a token that stops the supervisor when cancelled.
+    ///
+    /// Cancelling this token cancels every worker's token, and no worker is restarted afterwards.
+    /// Once every worker has returned, [`Supervisor::run`] returns [`SupervisorExit::Finished`].
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// Starts every worker and supervises them, blocking until they have all finished or the
+    /// supervisor escalates.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a worker thread could not be spawned. Every running worker is stopped
+    /// first.
+    ///
+    /// # Panics
+    ///
+    /// See [`chillpill::catch`]. Also resumes unwinding a worker's [`FatalPanic`], after every
+    /// worker has been stopped.
+    ///
+    /// [`chillpill::catch`]: crate::catch
+    /// [`FatalPanic`]: crate::FatalPanic
+    pub fn run(self) -> io::Result<SupervisorExit> {
+        let (tx, rx) = mpsc::channel();
+        let mut running: Vec<Option<RunningWorker>> = self.workers.iter().map(|_| None).collect();
+        let mut next_generation = 0;
+        let mut recent_panics: VecDeque<(Instant, WorkerPanic)> = VecDeque::new();
+
+        let mut spawn = |running: &mut Vec<Option<RunningWorker>>, index: usize| {
+            let generation = next_generation;
+            next_generation += 1;
+
+            match self.spawn_worker(index, generation, &tx) {
+                Ok(worker) => {
+                    running[index] = Some(worker);
+                    Ok(())
+                }
+                Err(err) => {
+                    running
+                        .iter_mut()
+                        .filter_map(Option::take)
+                        .for_each(RunningWorker::stop);
+                    Err(err)
+                }
+            }
+        };
+
+        for index in 0..self.workers.len() {
+            spawn(&mut running, index)?;
+        }
+
+        while running.iter().any(Option::is_some) {
+            let exit: WorkerExit = rx
+                .recv()
+                .expect("the supervisor holds a sender, so the channel cannot disconnect");
+
+            // Ignore exits from workers that were already stopped (and possibly restarted)
+            let is_current = running[exit.index]
+                .as_ref()
+                .is_some_and(|worker| worker.generation == exit.generation);
+            if !is_current {
+                continue;
+            }
+            if let Some(worker) = running[exit.index].take() {
+                let _ = worker.handle.join();
+            }
+
+            // Fatal panics stop every worker, and keep unwinding from the supervisor
+            let result = match exit.result {
+                Ok(result) => result,
+                Err(payload) => {
+                    running
+                        .iter_mut()
+                        .filter_map(Option::take)
+                        .for_each(RunningWorker::stop);
+                    std::panic::resume_unwind(payload);
+                }
+            };
+
+            // Workers that return without panicking are finished
+            let Err(panic_data) = result else {
+                continue;
+            };
+
+            let worker_panic = WorkerPanic {
+                worker: self.workers[exit.index].name.clone(),
+                panic_data,
+            };
+            if let Some(on_panic) = &self.on_panic {
+                on_panic(&worker_panic);
+            }
+
+            // Once the supervisor is cancelled, panicking workers are not restarted
+            if self.token.is_cancelled() {
+                continue;
+            }
+
+            // Escalate if the restart intensity is exceeded
+            let now = Instant::now();
+            recent_panics.push_back((now, worker_panic));
+            while recent_panics
+                .front()
+                .is_some_and(|(time, _)| now.duration_since(*time) > self.period)
+            {
+                recent_panics.pop_front();
+            }
+            if recent_panics.len() > self.max_restarts {
+                running
+                    .iter_mut()
+                    .filter_map(Option::take)
+                    .for_each(RunningWorker::stop);
+
+                let worker_panics: Vec<WorkerPanic> = recent_panics
+                    .into_iter()
+                    .map(|(_, worker_panic)| worker_panic)
+                    .collect();
+                if let Some(on_escalation) = &self.on_escalation {
+                    on_escalation(&worker_panics);
+                }
+
+                return Ok(SupervisorExit::Escalated);
+            }
+
+            // Stop and restart the affected workers. Workers that already finished stay finished.
+            for index in self.restart_range(exit.index) {
+                let was_running = running[index].take().map(RunningWorker::stop).is_some();
+                if index == exit.index || was_running {
+                    spawn(&mut running, index)?;
+                }
+            }
+        }
+
+        Ok(SupervisorExit::Finished)
+    }
+
+    /// Returns the indices of the workers to restart when the worker at `index` panics.
+    fn restart_range(&self, index: usize) -> Range<usize> {
+        match self.strategy {
+            RestartStrategy::OneForOne => index..index + 1,
+            RestartStrategy::OneForAll => 0..self.workers.len(),
+            RestartStrategy::RestForOne => index..self.workers.len(),
+        }
+    }
+
+    fn spawn_worker(
+        &self,
+        index: usize,
+        generation: u64,
+        tx: &mpsc::Sender<WorkerExit>,
+    ) -> io::Result<RunningWorker> {
+        let spec = &self.workers[index];
+        let f = Arc::clone(&spec.f);
+        let token = self.token.child_token();
+        let worker_token = token.clone();
+        let tx = tx.clone();
+
+        let handle = std::thread::Builder::new()
+            .name(spec.name.clone())
+            .spawn(move || {
+                // The caller asserted restarting workers is fine when adding them. Fatal panics
+                // pass through `catch_inner`, and are handed to the supervisor to resume.
+                let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                    catch_inner(
+                        AssertUnwindSafe(|| f(&worker_token)),
+                        CaptureBacktrace::Default,
+                    )
+                }));
+
+                // If the supervisor is gone, nobody is interested in this worker anymore
+                let _ = tx.send(WorkerExit {
+                    index,
+                    generation,
+                    result,
+                });
+            })?;
+
+        Ok(RunningWorker {
+            generation,
+            token,
+            handle,
+        })
+    }
+}
+
+impl Default for Supervisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Mutex,
+        atomic::{AtomicU32, Ordering},
+    };
+
+    use super::*;
+
+    /// Helper function that blocks until `token` is cancelled.
+    fn wait_for_cancellation(token: &CancellationToken) {
+        while !token.is_cancelled() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// This test ensures a panicking worker is restarted, and its panics are reported.
+    #[test]
+    fn one_for_one_restarts_worker() {
+        let starts = Arc::new(AtomicU32::new(0));
+        let starts_copy = Arc::clone(&starts);
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let reported_copy = Arc::clone(&reported);
+
+        let exit = Supervisor::new()
+            .on_panic(move |worker_panic| {
+                reported_copy.lock().unwrap().push((
+                    worker_panic.worker.clone(),
+                    worker_panic
+                        .panic_data
+                        .payload_as_string()
+                        .map(String::from),
+                ));
+            })
+            .worker("flaky", move |_| {
+                let start = starts_copy.fetch_add(1, Ordering::SeqCst) + 1;
+                assert!(start > 2, "start {start} failed");
+            })
+            .run()
+            .unwrap();
+
+        assert_eq!(exit, SupervisorExit::Finished);
+        assert_eq!(starts.load(Ordering::SeqCst), 3);
+        assert_eq!(
+            *reported.lock().unwrap(),
+            [
+                (String::from("flaky"), Some(String::from("start 1 failed"))),
+                (String::from("flaky"), Some(String::from("start 2 failed"))),
+            ]
+        );
+    }
+
+    /// This test ensures the supervisor escalates (stopping every worker) once the restart
+    /// intensity is exceeded.
+    #[test]
+    fn escalates_when_intensity_exceeded() {
+        let escalated = Arc::new(Mutex::new(Vec::new()));
+        let escalated_copy = Arc::clone(&escalated);
+
+        let exit = Supervisor::new()
+            .intensity(2, Duration::from_secs(60))
+            .on_escalation(move |worker_panics| {
+                *escalated_copy.lock().unwrap() = worker_panics
+                    .iter()
+                    .map(|worker_panic| worker_panic.worker.clone())
+                    .collect();
+            })
+            .worker("broken", |_| panic!("always broken"))
+            .worker("healthy", wait_for_cancellation)
+            .run()
+            .unwrap();
+
+        assert_eq!(exit, SupervisorExit::Escalated);
+        assert_eq!(*escalated.lock().unwrap(), ["broken", "broken", "broken"]);
+    }
+
+    /// This test ensures [`RestartStrategy::OneForAll`] restarts the siblings of a panicking
+    /// worker, and [`RestartStrategy::RestForOne`] restarts only the workers added after it.
+    #[test]
+    fn restart_strategies_restart_siblings() {
+        for (strategy, expected_starts) in [
+            (RestartStrategy::OneForAll, [2, 2, 2]),
+            (RestartStrategy::RestForOne, [1, 2, 2]),
+        ] {
+            let supervisor = Supervisor::new().strategy(strategy);
+            let shutdown = supervisor.cancellation_token();
+            let starts: Arc<[AtomicU32; 3]> = Arc::new(Default::default());
+
+            let starts_copy = Arc::clone(&starts);
+            let supervisor = supervisor.worker("first", move |token| {
+                starts_copy[0].fetch_add(1, Ordering::SeqCst);
+                wait_for_cancellation(token);
+            });
+            let starts_copy = Arc::clone(&starts);
+            let supervisor = supervisor.worker("second", move |token| {
+                // Panic the first time, after every worker has started
+                if starts_copy[1].fetch_add(1, Ordering::SeqCst) == 0 {
+                    while starts_copy[2].load(Ordering::SeqCst) == 0 {
+                        std::thread::sleep(Duration::from_millis(1));
+                    }
+                    panic!("second worker panicked");
+                }
+                wait_for_cancellation(token);
+            });
+            let starts_copy = Arc::clone(&starts);
+            let shutdown_copy = shutdown.clone();
+            let supervisor = supervisor.worker("third", move |token| {
+                // Shut everything down once restarted
+                if starts_copy[2].fetch_add(1, Ordering::SeqCst) == 1 {
+                    shutdown_copy.cancel();
+                }
+                wait_for_cancellation(token);
+            });
+
+            assert_eq!(supervisor.run().unwrap(), SupervisorExit::Finished);
+            let starts = starts
+                .each_ref()
+                .map(|starts| starts.load(Ordering::SeqCst));
+            assert_eq!(starts, expected_starts, "{strategy:?}");
+        }
+    }
+
+    /// This test ensures a fatal panic stops every worker, and is resumed by `run`.
+    #[test]
+    fn fatal_panic_resumed_by_run() {
+        let supervisor = Supervisor::new()
+            .worker("waiting", wait_for_cancellation)
+            .worker("fatal", |_| crate::panic_fatal!("fatal worker"));
+
+        let payload = std::panic::catch_unwind(AssertUnwindSafe(|| supervisor.run())).unwrap_err();
+        assert!(payload.is::<crate::FatalPanic>());
+    }
+}
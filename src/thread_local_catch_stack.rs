@@ -1,6 +1,9 @@
-use std::{backtrace::Backtrace, cell::RefCell};
+use std::{backtrace::Backtrace, cell::RefCell, panic::PanicHookInfo, sync::Arc, time::Instant};
 
-use crate::PanicLocation;
+use crate::{PanicLocation, PanicReporter, stats};
+
+/// A predicate deciding whether a `catch` call should catch a panic.
+pub type CatchFilter = dyn Fn(&PanicHookInfo<'_>) -> bool + Send + Sync;
 
 thread_local! {
     /// A thread-local stack of [`CatchStackFrame`]s, used to enable communication between
@@ -44,11 +47,15 @@ thread_local! {
     /// - Otherwise, uses the top stack frame to determine whether to capture a backtrace, and as a
     ///   storage location to smuggle out the panic location and captured backtrace.
     ///
+    /// Frames may also have a filter, in which case the panic hook first asks the filter whether
+    /// the frame's `catch` call should catch the panic. If not, the frame is marked as rejecting
+    /// the panic (so `catch` resumes unwinding it), and the hook moves on to the next frame down the
+    /// stack, delegating to the real hook if no frame accepts the panic.
+    ///
     /// [`chillpill::catch`]: crate::catch
     pub static THREAD_LOCAL_CATCH_STACK: RefCell<Vec<CatchStackFrame>> = const { RefCell::new(Vec::new()) };
 }
 
-#[derive(Debug)]
 pub struct CatchStackFrame {
     /// When to capture backtrace - provided by the call to `catch`.
     pub capture_backtrace: CaptureBacktrace,
@@ -70,6 +77,53 @@ pub struct CatchStackFrame {
     /// this may still be a disabled backtrace even after a panic if the panic hook is not invoked
     /// for the panic (e.g., via `std::panic::resume_unwind`).
     pub backtrace: Backtrace,
+
+    /// Which panics to catch - provided by the call to `catch`. [`None`] means all panics.
+    pub filter: Option<Arc<CatchFilter>>,
+
+    /// Whether `filter` rejected the most recent hook-invoking panic - set in our custom panic
+    /// hook on panics.
+    ///
+    /// A rejected panic must not be caught by this frame's `catch` call, which instead resumes
+    /// unwinding it. In that case, the panic location and backtrace are recorded in the first frame
+    /// further down the stack that accepted the panic (if any), not in this frame.
+    pub rejected: bool,
+
+    /// When the `catch` call started - used to measure how long it ran before catching a panic, if
+    /// catch durations are being recorded.
+    pub started: Option<Instant>,
+
+    /// A label identifying the `catch` call to panic subscribers - provided by the call to `catch`.
+    pub label: Option<Arc<str>>,
+
+    /// Context about where the panic happened, like the current `tracing` span - written by the
+    /// panic hook on panics, and later moved into [`PanicData::context`].
+    ///
+    /// [`PanicData::context`]: crate::PanicData::context
+    pub context: Vec<(String, String)>,
+
+    /// Key-value tags to include in reports of caught panics - provided by the call to `catch`.
+    pub tags: Option<Arc<[(String, String)]>>,
+
+    /// The reporter to report caught panics to - provided by the call to `catch`.
+    pub reporter: Option<Arc<dyn PanicReporter>>,
+}
+
+impl std::fmt::Debug for CatchStackFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CatchStackFrame")
+            .field("capture_backtrace", &self.capture_backtrace)
+            .field("location", &self.location)
+            .field("backtrace", &self.backtrace)
+            .field("filter", &self.filter.as_ref().map(|_| ".."))
+            .field("rejected", &self.rejected)
+            .field("started", &self.started)
+            .field("label", &self.label)
+            .field("context", &self.context)
+            .field("tags", &self.tags)
+            .field("reporter", &self.reporter.as_ref().map(|_| ".."))
+            .finish()
+    }
 }
 
 impl CatchStackFrame {
@@ -78,6 +132,13 @@ impl CatchStackFrame {
             capture_backtrace,
             location: None,
             backtrace: Backtrace::disabled(),
+            filter: None,
+            rejected: false,
+            started: stats::catch_durations_enabled().then(Instant::now),
+            label: None,
+            context: Vec::new(),
+            tags: None,
+            reporter: None,
         }
     }
 }
@@ -96,3 +157,14 @@ pub(crate) enum CaptureBacktrace {
     /// Never capture a backtrace.
     Never,
 }
+
+impl CaptureBacktrace {
+    /// Captures a backtrace of the current thread, if this says to.
+    pub fn capture(self) -> Backtrace {
+        match self {
+            Self::Always => Backtrace::force_capture(),
+            Self::Default => Backtrace::capture(),
+            Self::Never => Backtrace::disabled(),
+        }
+    }
+}
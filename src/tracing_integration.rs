@@ -0,0 +1,164 @@
+//! Integration with [`tracing`], enabled by the `tracing` feature.
+
+use std::{
+    backtrace::{Backtrace, BacktraceStatus},
+    fmt::Debug,
+    panic::PanicHookInfo,
+};
+
+use tracing::{
+    Id, Subscriber,
+    field::{Field, Visit},
+    span::{Attributes, Record},
+};
+use tracing_subscriber::{
+    Registry,
+    layer::{Context, Layer},
+    registry::LookupSpan,
+};
+
+use crate::payload_formatter::payload_to_string;
+
+/// A [`tracing_subscriber`] layer that records the fields of every span, so that panics can include
+/// the fields of the span they happened in as context (see [`PanicData::context`]).
+///
+/// Without this layer, the context of a panic only includes the name of the current span. The
+/// subscriber must be built on a [`Registry`].
+///
+/// # Example
+///
+/// ```
+/// use tracing_subscriber::layer::SubscriberExt;
+///
+/// let subscriber = tracing_subscriber::registry().with(chillpill::PanicContextLayer::new());
+/// tracing::subscriber::with_default(subscriber, || {
+///     let _span = tracing::info_span!("request", user_id = 42).entered();
+///
+///     let panic_data = chillpill::catch(|| panic!("oh no")).unwrap_err();
+///     assert_eq!(
+///         panic_data.context(),
+///         [
+///             (String::from("span"), String::from("request")),
+///             (String::from("user_id"), String::from("42")),
+///         ]
+///     );
+/// });
+/// ```
+///
+/// [`PanicData::context`]: crate::PanicData::context
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PanicContextLayer {
+    _private: (),
+}
+
+impl PanicContextLayer {
+    /// Creates the layer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for PanicContextLayer {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        let mut fields = SpanFields(Vec::new());
+        attrs.record(&mut fields);
+        span.extensions_mut().replace(fields);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        let mut extensions = span.extensions_mut();
+        if let Some(fields) = extensions.get_mut::<SpanFields>() {
+            values.record(fields);
+        }
+    }
+}
+
+/// The fields of a span, as recorded by [`PanicContextLayer`].
+struct SpanFields(Vec<(String, String)>);
+
+impl SpanFields {
+    fn set(&mut self, field: &Field, value: String) {
+        match self.0.iter_mut().find(|(name, _)| name == field.name()) {
+            Some((_, existing)) => *existing = value,
+            None => self.0.push((field.name().to_string(), value)),
+        }
+    }
+}
+
+impl Visit for SpanFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.set(field, value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.set(field, format!("{value:?}"));
+    }
+}
+
+/// Returns the context of the current span: its name (under the `"span"` key), followed by its
+/// fields if they were recorded by [`PanicContextLayer`]. Returns no context outside of any span.
+pub(crate) fn current_span_context() -> Vec<(String, String)> {
+    let span = tracing::Span::current();
+    let Some(metadata) = span.metadata() else {
+        return Vec::new();
+    };
+
+    let mut context = vec![(String::from("span"), metadata.name().to_string())];
+    span.with_subscriber(|(id, dispatch)| {
+        let Some(span) = dispatch
+            .downcast_ref::<Registry>()
+            .and_then(|registry| registry.span(id))
+        else {
+            return;
+        };
+
+        if let Some(fields) = span.extensions().get::<SpanFields>() {
+            context.extend(fields.0.iter().cloned());
+        }
+    });
+
+    context
+}
+
+/// Returns the text of a backtrace, or [`None`] if it was not captured.
+pub(crate) fn captured_backtrace(backtrace: &Backtrace) -> Option<String> {
+    (backtrace.status() == BacktraceStatus::Captured).then(|| backtrace.to_string())
+}
+
+/// Records a panic as an `ERROR` event in the current span, with the `panic.message`,
+/// `panic.file`, `panic.line`, `panic.column`, `panic.backtrace`, and `panic.caught` fields.
+///
+/// `backtrace` is only called if the event is enabled, and the `panic.backtrace` field is only
+/// recorded if it returns a backtrace (see [`captured_backtrace`]).
+pub(crate) fn record_panic(
+    info: &PanicHookInfo<'_>,
+    caught: bool,
+    backtrace: impl FnOnce() -> Option<String>,
+) {
+    if !tracing::enabled!(target: "chillpill", tracing::Level::ERROR) {
+        return;
+    }
+
+    let message = payload_to_string(info.payload());
+    let location = info.location();
+    let backtrace = backtrace();
+    tracing::event!(
+        target: "chillpill",
+        tracing::Level::ERROR,
+        panic.message = message.as_deref().unwrap_or("Box<dyn Any>"),
+        panic.file = location.map(std::panic::Location::file),
+        panic.line = location.map(std::panic::Location::line),
+        panic.column = location.map(std::panic::Location::column),
+        panic.backtrace = backtrace.as_deref(),
+        panic.caught = caught,
+        "panic"
+    );
+}
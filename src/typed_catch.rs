@@ -0,0 +1,236 @@
+use std::{any::Any, panic::UnwindSafe};
+
+use crate::{Catcher, TypedPanic};
+
+/// Invokes a closure, catching only panics whose payload is of type `E`.
+///
+/// This is useful when panicking with a structured payload (via [`std::panic::panic_any`]) as an
+/// escape hatch. Panics with a payload of type `E` are caught like [`chillpill::catch`] would, and
+/// all other panics keep unwinding, untouched (see [`Catcher::filter`]).
+///
+/// # Example
+///
+/// ```
+/// #[derive(Debug, PartialEq)]
+/// struct MyError {
+///     code: u32,
+/// }
+///
+/// let typed_panic = chillpill::catch_typed::<MyError, _, _>(|| {
+///     std::panic::panic_any(MyError { code: 7 });
+/// })
+/// .unwrap_err();
+/// assert_eq!(typed_panic.payload, MyError { code: 7 });
+/// ```
+///
+/// # Errors
+///
+/// Returns an error with the typed panic data if the provided closure panics with a payload of type
+/// `E`.
+///
+/// # Panics
+///
+/// See [`chillpill::catch`]. Also resumes unwinding any panic with a payload of another type.
+///
+/// [`chillpill::catch`]: crate::catch
+pub fn catch_typed<E: Any + Send, F: FnOnce() -> R + UnwindSafe, R>(
+    f: F,
+) -> Result<R, TypedPanic<E>> {
+    Catcher::new()
+        .filter(|info| info.payload().is::<E>())
+        .catch(f)
+        .map_err(|panic_data| {
+            // Panics that don't invoke the panic hook bypass the filter, so they still need to be
+            // checked here
+            panic_data
+                .downcast()
+                .unwrap_or_else(|panic_data| std::panic::resume_unwind(panic_data.payload))
+        })
+}
+
+/// Invokes a block, catching panics whose payload matches one of several types, and handling each
+/// type with its own arm.
+///
+/// Each arm has the form `catch (name: Type) => expression`, and binds the panic payload (of type
+/// `Type`) to `name`. The special type `str` matches both [`&str`](str) and [`String`] payloads,
+/// binding the payload as a `&str`. Arms are checked in order, and panics that match none of them
+/// keep unwinding, untouched (see [`Catcher::filter`]).
+///
+/// The block runs in a closure within a [`chillpill::catch`], so it must be [`UnwindSafe`], and
+/// `return` and `?` within it apply to that closure.
+///
+/// # Example
+///
+/// ```
+/// #[derive(Debug)]
+/// struct MyError {
+///     code: u32,
+/// }
+///
+/// let code = chillpill::try_catch! {
+///     {
+///         std::panic::panic_any(MyError { code: 7 });
+///     }
+///     catch (e: MyError) => e.code,
+///     catch (msg: str) => {
+///         eprintln!("unexpected panic: {msg}");
+///         0
+///     },
+/// };
+/// assert_eq!(code, 7);
+/// ```
+///
+/// [`chillpill::catch`]: crate::catch
+/// [`UnwindSafe`]: std::panic::UnwindSafe
+#[macro_export]
+macro_rules! try_catch {
+    ($body:block $($arms:tt)+) => {
+        match $crate::Catcher::new()
+            .filter(|info| {
+                let payload = info.payload();
+                $crate::__try_catch_accepts!(payload; $($arms)+)
+            })
+            .catch(|| $body)
+        {
+            ::core::result::Result::Ok(value) => value,
+            ::core::result::Result::Err(panic_data) => {
+                $crate::__try_catch_dispatch!(panic_data; $($arms)+)
+            }
+        }
+    };
+}
+
+/// Implementation detail of [`try_catch!`] - evaluates to whether any arm accepts `$payload`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __try_catch_accepts {
+    ($payload:ident; catch ($name:ident : str) => $arm:expr $(, $($rest:tt)*)?) => {
+        $payload.is::<&str>()
+            || $payload.is::<::std::string::String>()
+            || $crate::__try_catch_accepts!($payload; $($($rest)*)?)
+    };
+    ($payload:ident; catch ($name:ident : $ty:ty) => $arm:expr $(, $($rest:tt)*)?) => {
+        $payload.is::<$ty>() || $crate::__try_catch_accepts!($payload; $($($rest)*)?)
+    };
+    ($payload:ident;) => {
+        false
+    };
+}
+
+/// Implementation detail of [`try_catch!`] - evaluates the first arm matching `$panic_data`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __try_catch_dispatch {
+    ($panic_data:ident; catch ($name:ident : str) => $arm:expr $(, $($rest:tt)*)?) => {{
+        // Only `&str` and `String` payloads match `str`, like in `__try_catch_accepts!`
+        let is_string = $panic_data.payload.is::<&str>()
+            || $panic_data.payload.is::<::std::string::String>();
+        let result = if is_string {
+            $panic_data.payload_into_string()
+        } else {
+            ::core::result::Result::Err($panic_data)
+        };
+        match result {
+            ::core::result::Result::Ok($name) => {
+                let $name: &str = &$name;
+                $arm
+            }
+            ::core::result::Result::Err($panic_data) => {
+                $crate::__try_catch_dispatch!($panic_data; $($($rest)*)?)
+            }
+        }
+    }};
+    ($panic_data:ident; catch ($name:ident : $ty:ty) => $arm:expr $(, $($rest:tt)*)?) => {
+        match $panic_data.downcast::<$ty>() {
+            ::core::result::Result::Ok(typed_panic) => {
+                let $name: $ty = typed_panic.payload;
+                $arm
+            }
+            ::core::result::Result::Err($panic_data) => {
+                $crate::__try_catch_dispatch!($panic_data; $($($rest)*)?)
+            }
+        }
+    };
+    // Panics that don't invoke the panic hook bypass the filter, so they may match no arm
+    ($panic_data:ident;) => {
+        ::std::panic::resume_unwind($panic_data.payload)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct MyError {
+        code: u32,
+    }
+
+    /// This test ensures [`catch_typed`] catches a panic with a payload of the given type.
+    #[test]
+    fn catch_typed_catches_matching_type() {
+        let typed_panic = catch_typed::<MyError, _, _>(|| {
+            std::panic::panic_any(MyError { code: 7 });
+        })
+        .unwrap_err();
+
+        assert_eq!(typed_panic.payload, MyError { code: 7 });
+        assert_eq!(typed_panic.location.unwrap().file, file!());
+    }
+
+    /// This test ensures [`catch_typed`] lets a panic with a payload of another type keep
+    /// unwinding.
+    #[test]
+    fn catch_typed_reraises_other_types() {
+        let panic_data = crate::catch(|| {
+            let _ = catch_typed::<MyError, _, _>(|| panic!("not a MyError"));
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            panic_data.payload_as_string().as_deref(),
+            Some("not a MyError")
+        );
+    }
+
+    /// This test ensures [`try_catch!`] runs the first arm matching the payload type.
+    #[test]
+    fn try_catch_dispatches_by_type() {
+        let handle = |f: fn()| {
+            try_catch! {
+                {
+                    f();
+                    String::from("no panic")
+                }
+                catch (e: MyError) => format!("MyError {}", e.code),
+                catch (msg: str) => format!("message {msg}"),
+                catch (n: u32) => format!("u32 {n}"),
+            }
+        };
+
+        assert_eq!(handle(|| ()), "no panic");
+        assert_eq!(
+            handle(|| std::panic::panic_any(MyError { code: 7 })),
+            "MyError 7"
+        );
+        assert_eq!(handle(|| panic!("static")), "message static");
+        assert_eq!(handle(|| panic!("{}", "formatted")), "message formatted");
+        assert_eq!(handle(|| std::panic::panic_any(3_u32)), "u32 3");
+    }
+
+    /// This test ensures [`try_catch!`] lets a panic matching no arm keep unwinding.
+    #[test]
+    fn try_catch_reraises_unmatched() {
+        let panic_data = crate::catch(|| {
+            try_catch! {
+                {
+                    std::panic::panic_any(3_u64);
+                }
+                catch (_e: MyError) => (),
+            }
+        })
+        .unwrap_err();
+
+        assert_eq!(*panic_data.payload.downcast::<u64>().unwrap(), 3);
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    backtrace::Backtrace,
+    cell::OnceCell,
+    panic::PanicHookInfo,
+    sync::{Arc, PoisonError, RwLock},
+    time::Duration,
+};
+
+use crate::{PanicReport, PanicReporter, crash_report, panic_hook};
+
+type UncaughtHandler = Arc<dyn Fn(&PanicReport) -> UncaughtAction + Send + Sync>;
+
+/// The handler invoked by the chillpill panic hook for panics not caught by `chillpill::catch`.
+static UNCAUGHT_HANDLER: RwLock<Option<UncaughtHandler>> = RwLock::new(None);
+
+/// The reporter for panics not caught by `chillpill::catch`, run before the uncaught handler.
+static UNCAUGHT_REPORTER: RwLock<Option<Arc<dyn PanicReporter>>> = RwLock::new(None);
+
+/// How long to wait for the uncaught reporter to deliver its reports before chillpill aborts the
+/// process.
+const ABORT_FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// What to do with an uncaught panic after the uncaught panic handler has run.
+///
+/// See [`set_uncaught_handler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UncaughtAction {
+    /// Forward the panic to the panic hook beneath the chillpill panic hook (by default, the
+    /// standard library's panic hook, which prints the panic message to `stderr`).
+    Forward,
+
+    /// Do nothing further. The panic continues to unwind as usual, but no panic hook output is
+    /// produced.
+    Suppress,
+
+    /// Immediately abort the process with [`std::process::abort`].
+    Abort,
+}
+
+/// Registers a handler for every panic, on any thread, that is not caught by [`chillpill::catch`],
+/// replacing any previously registered handler.
+///
+/// The handler receives a [`PanicReport`] describing the panic, and decides what happens next by
+/// returning an [`UncaughtAction`]. This is useful for logging all crashes in one place, in one
+/// consistent format.
+///
+/// The handler runs inside the panic hook, so it must not panic - doing so aborts the process.
+///
+/// # Panics
+///
+/// Panics if the chillpill panic hook is not yet installed and this thread is currently unwinding
+/// from a panic.
+///
+/// [`chillpill::catch`]: crate::catch
+pub fn set_uncaught_handler(
+    handler: impl Fn(&PanicReport) -> UncaughtAction + Send + Sync + 'static,
+) {
+    if let Err(()) = panic_hook::install_if_not_installed() {
+        panic!("the chillpill panic hook must not be installed from a panicking thread");
+    }
+
+    *UNCAUGHT_HANDLER
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(handler));
+}
+
+/// Unregisters the uncaught panic handler (see [`set_uncaught_handler`]), returning it if one was
+/// registered.
+///
+/// Without a handler, uncaught panics are always forwarded to the panic hook beneath the chillpill
+/// panic hook.
+#[expect(
+    clippy::type_complexity,
+    reason = "the handler type is spelled out so that it shows in the public signature"
+)]
+pub fn take_uncaught_handler() -> Option<Box<dyn Fn(&PanicReport) -> UncaughtAction + Send + Sync>>
+{
+    let handler = UNCAUGHT_HANDLER
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take()?;
+
+    Some(Box::new(move |report| handler(report)))
+}
+
+/// Registers a reporter for every panic, on any thread, that is not caught by
+/// [`chillpill::catch`], replacing any previously registered reporter.
+///
+/// The reporter runs before the uncaught panic handler (see [`set_uncaught_handler`]), if any, and
+/// regardless of the action it returns. To report to several destinations, combine reporters with
+/// [`FanOut`].
+///
+/// # Panics
+///
+/// Panics if the chillpill panic hook is not yet installed and this thread is currently unwinding
+/// from a panic.
+///
+/// [`chillpill::catch`]: crate::catch
+/// [`FanOut`]: crate::FanOut
+pub fn set_uncaught_reporter(reporter: impl PanicReporter + 'static) {
+    if let Err(()) = panic_hook::install_if_not_installed() {
+        panic!("the chillpill panic hook must not be installed from a panicking thread");
+    }
+
+    *UNCAUGHT_REPORTER
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(reporter));
+}
+
+/// Unregisters the uncaught panic reporter (see [`set_uncaught_reporter`]), returning it if one
+/// was registered.
+pub fn take_uncaught_reporter() -> Option<Box<dyn PanicReporter>> {
+    let reporter = UNCAUGHT_REPORTER
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take()?;
+
+    Some(Box::new(reporter))
+}
+
+/// Waits for the uncaught panic reporter (see [`set_uncaught_reporter`]) to deliver its reports.
+///
+/// Returns whether every report was delivered (or there is no reporter) before `timeout` passed.
+/// This already happens (with a short timeout) before chillpill aborts the process, and when
+/// [`chillpill::main`] returns. Call it before exiting the process by other means (like
+/// [`std::process::exit`]) to avoid losing reports still being delivered in the background.
+///
+/// See [`PanicReporter::flush`].
+///
+/// [`chillpill::main`]: crate::main()
+pub fn flush_uncaught_reporter(timeout: Duration) -> bool {
+    let reporter = UNCAUGHT_REPORTER
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+
+    reporter.is_none_or(|reporter| reporter.flush(timeout))
+}
+
+/// Returns the backtrace of an uncaught panic, capturing it on first use, so that it is captured at
+/// most once per panic.
+pub(crate) fn backtrace(backtrace: &OnceCell<Arc<Backtrace>>) -> &Arc<Backtrace> {
+    backtrace.get_or_init(|| Arc::new(Backtrace::capture()))
+}
+
+/// Handles a panic not caught by `chillpill::catch`. Called from the chillpill panic hook.
+///
+/// `backtrace` is the backtrace of the panic, captured on first use (see [`backtrace`]).
+pub(crate) fn handle_uncaught_panic(
+    info: &PanicHookInfo<'_>,
+    backtrace: &OnceCell<Arc<Backtrace>>,
+) {
+    let action = {
+        // Call the reporter and handler without their locks held, so that they are free to replace
+        // themselves (and so that a slow reporter doesn't hold up replacing either)
+        let reporter = UNCAUGHT_REPORTER
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let handler = UNCAUGHT_HANDLER
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+
+        // Build the report once, and only if something will look at it
+        let report = (reporter.is_some() || handler.is_some())
+            .then(|| PanicReport::from_hook_info(info, Arc::clone(self::backtrace(backtrace))));
+        if let (Some(reporter), Some(report)) = (reporter.as_ref(), report.as_ref()) {
+            reporter.report(report);
+        }
+
+        match (handler.as_ref(), report.as_ref()) {
+            (Some(handler), Some(report)) => handler(report),
+            _ => UncaughtAction::Forward,
+        }
+    };
+
+    match action {
+        UncaughtAction::Forward => panic_hook::call_previous_hook(info),
+        UncaughtAction::Suppress => {}
+        UncaughtAction::Abort => abort(info, "the uncaught panic handler requested an abort"),
+    }
+}
+
+/// Aborts the process because of the panic described by `info`, after writing a crash report and
+/// giving the uncaught panic reporter the chance to deliver its reports.
+///
+/// Reporters are only flushed here, where the process is certainly about to end, so that an
+/// uncaught panic that does not end the process never blocks on them.
+pub(crate) fn abort(info: &PanicHookInfo<'_>, reason: &str) -> ! {
+    crash_report::report_abort(info, reason);
+    flush_uncaught_reporter(ABORT_FLUSH_TIMEOUT);
+    std::process::abort();
+}
@@ -0,0 +1,460 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Condvar, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use crate::{Backoff, PanicReport, PanicReporter};
+
+/// How long the sender thread waits for the collector to accept a report before reconnecting.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Which report a [`UnixSocketReporter`] drops when its queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DropPolicy {
+    /// Drop the new report, keeping the reports already queued.
+    Newest,
+
+    /// Drop the oldest queued report to make room for the new report.
+    Oldest,
+}
+
+/// Configuration for a [`UnixSocketReporter`].
+#[derive(Debug, Clone)]
+pub struct UnixSocketReporterConfig {
+    path: PathBuf,
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+    backoff: Backoff,
+}
+
+impl UnixSocketReporterConfig {
+    /// Creates a configuration for a reporter that sends reports to the Unix socket at `path`.
+    ///
+    /// By default, up to 256 reports are queued, the newest report is dropped when the queue is
+    /// full, and reconnection attempts back off exponentially from 50 milliseconds to 5 seconds.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            queue_capacity: 256,
+            drop_policy: DropPolicy::Newest,
+            backoff: Backoff::Exponential {
+                initial: Duration::from_millis(50),
+                factor: 2,
+                max: Duration::from_secs(5),
+            },
+        }
+    }
+
+    /// Sets the maximum number of reports queued while waiting to be sent.
+    ///
+    /// A capacity of zero is treated as one.
+    #[must_use]
+    pub fn queue_capacity(mut self, queue_capacity: usize) -> Self {
+        self.queue_capacity = queue_capacity.max(1);
+        self
+    }
+
+    /// Sets which report is dropped when a report arrives while the queue is full.
+    #[must_use]
+    pub fn drop_policy(mut self, drop_policy: DropPolicy) -> Self {
+        self.drop_policy = drop_policy;
+        self
+    }
+
+    /// Sets how long to wait between failed attempts to connect (or send) to the socket.
+    #[must_use]
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Starts the reporter's background sender thread, which connects to the socket.
+    ///
+    /// The socket does not need to exist yet - the sender keeps trying to connect, according to the
+    /// configured backoff.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sender thread could not be spawned.
+    pub fn start(self) -> io::Result<UnixSocketReporter> {
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(Queue {
+                reports: VecDeque::new(),
+                sending: false,
+                shutdown: false,
+            }),
+            changed: Condvar::new(),
+            queue_capacity: self.queue_capacity,
+            drop_policy: self.drop_policy,
+            dropped: AtomicU64::new(0),
+        });
+
+        {
+            let shared = Arc::clone(&shared);
+            let path = self.path.clone();
+            std::thread::Builder::new()
+                .name(String::from("chillpill-unix-socket-reporter"))
+                .spawn(move || run_sender(&shared, &path, self.backoff))?;
+        }
+
+        Ok(UnixSocketReporter {
+            shared,
+            path: self.path,
+        })
+    }
+}
+
+/// A reporter that sends reports to a local collector listening on a Unix domain socket.
+///
+/// Each report is sent as a single line of JSON (see [`PanicReport::to_json_line`]), so the stream
+/// is in the [JSON Lines](https://jsonlines.org) format.
+///
+/// Reporting never blocks on the socket: reports are added to a bounded queue, and sent by a
+/// background thread. If the collector is down, the thread reconnects with backoff, and reports
+/// that arrive while the queue is full are dropped according to the [`DropPolicy`].
+///
+/// Since reports are sent in the background, they can be lost if the process exits first. To wait
+/// for them to be sent, call [`PanicReporter::flush`]. For the uncaught panic reporter, this
+/// happens automatically (with a bounded timeout) before chillpill aborts the process, and when
+/// [`chillpill::main`] returns (see [`flush_uncaught_reporter`]). When the reporter is dropped, the
+/// thread goes on to send whatever is queued (if it is connected), then exits, without the drop
+/// waiting for it.
+///
+/// # Example
+///
+/// ```no_run
+/// use chillpill::UnixSocketReporter;
+///
+/// let reporter = UnixSocketReporter::start("/run/collector.sock").unwrap();
+/// chillpill::set_uncaught_reporter(reporter);
+/// ```
+///
+/// [`chillpill::main`]: crate::main()
+/// [`flush_uncaught_reporter`]: crate::flush_uncaught_reporter
+pub struct UnixSocketReporter {
+    shared: Arc<Shared>,
+    path: PathBuf,
+}
+
+impl std::fmt::Debug for UnixSocketReporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UnixSocketReporter")
+            .field("path", &self.path)
+            .field("queue_capacity", &self.shared.queue_capacity)
+            .field("drop_policy", &self.shared.drop_policy)
+            .field("dropped", &self.dropped())
+            .finish_non_exhaustive()
+    }
+}
+
+impl UnixSocketReporter {
+    /// Starts a reporter that sends reports to the Unix socket at `path`, with the default
+    /// configuration (see [`UnixSocketReporterConfig`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sender thread could not be spawned.
+    pub fn start(path: impl Into<PathBuf>) -> io::Result<Self> {
+        UnixSocketReporterConfig::new(path).start()
+    }
+
+    /// Returns the number of reports dropped so far, either because the queue was full or because
+    /// they could not be sent.
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of reports currently queued, waiting to be sent.
+    pub fn queued(&self) -> usize {
+        self.shared.lock().reports.len()
+    }
+}
+
+impl PanicReporter for UnixSocketReporter {
+    fn report(&self, report: &PanicReport) {
+        let dropped = enqueue(
+            &mut self.shared.lock().reports,
+            report.clone(),
+            self.shared.queue_capacity,
+            self.shared.drop_policy,
+        );
+        if dropped {
+            self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+
+        self.shared.changed.notify_all();
+    }
+
+    /// Waits until every queued report has been sent, or until `timeout` has passed, returning
+    /// whether every queued report was sent.
+    fn flush(&self, timeout: Duration) -> bool {
+        let (queue, _) = self
+            .shared
+            .changed
+            .wait_timeout_while(self.shared.lock(), timeout, |queue| !queue.is_flushed())
+            .unwrap_or_else(PoisonError::into_inner);
+
+        queue.is_flushed()
+    }
+}
+
+impl Drop for UnixSocketReporter {
+    fn drop(&mut self) {
+        // Don't wait for the sender thread to finish - the reporter may be dropped while a lock is
+        // held (like when replacing the uncaught reporter), and sending can take a while
+        self.shared.lock().shutdown = true;
+        self.shared.changed.notify_all();
+    }
+}
+
+/// The state shared between a [`UnixSocketReporter`] and its sender thread.
+struct Shared {
+    queue: Mutex<Queue>,
+
+    /// Notified when a report is queued or sent, or the reporter is shutting down.
+    changed: Condvar,
+
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+    dropped: AtomicU64,
+}
+
+struct Queue {
+    reports: VecDeque<PanicReport>,
+
+    /// Whether the sender thread is sending a report it took off the queue.
+    sending: bool,
+
+    shutdown: bool,
+}
+
+impl Queue {
+    /// Returns whether every report has been sent.
+    fn is_flushed(&self) -> bool {
+        self.reports.is_empty() && !self.sending
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Waits for `timeout`, returning early (with `true`) if the reporter is shutting down.
+    fn wait_for_shutdown(&self, timeout: Duration) -> bool {
+        let (queue, _) = self
+            .changed
+            .wait_timeout_while(self.lock(), timeout, |queue| !queue.shutdown)
+            .unwrap_or_else(PoisonError::into_inner);
+
+        queue.shutdown
+    }
+}
+
+/// Adds `item` to a queue holding at most `capacity` items, dropping an item according to
+/// `drop_policy` if the queue is full. Returns whether an item was dropped.
+fn enqueue<T>(queue: &mut VecDeque<T>, item: T, capacity: usize, drop_policy: DropPolicy) -> bool {
+    if queue.len() < capacity {
+        queue.push_back(item);
+        return false;
+    }
+
+    match drop_policy {
+        DropPolicy::Newest => {}
+        DropPolicy::Oldest => {
+            queue.pop_front();
+            queue.push_back(item);
+        }
+    }
+
+    true
+}
+
+/// The sender thread of a [`UnixSocketReporter`], which sends queued reports to the socket at
+/// `path` until the reporter shuts down.
+fn run_sender(shared: &Shared, path: &Path, backoff: Backoff) {
+    let mut stream: Option<UnixStream> = None;
+    let mut failures: u32 = 0;
+
+    loop {
+        let report = {
+            let mut queue = shared
+                .changed
+                .wait_while(shared.lock(), |queue| {
+                    queue.reports.is_empty() && !queue.shutdown
+                })
+                .unwrap_or_else(PoisonError::into_inner);
+
+            match queue.reports.pop_front() {
+                Some(report) => {
+                    queue.sending = true;
+                    report
+                }
+                None => return,
+            }
+        };
+
+        let sent = send(&mut stream, path, &report).is_ok();
+
+        let mut queue = shared.lock();
+        queue.sending = false;
+        if !sent {
+            // Put the report back to retry it, unless the queue filled up in the meantime
+            if queue.reports.len() < shared.queue_capacity {
+                queue.reports.push_front(report);
+            } else {
+                shared.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        drop(queue);
+        shared.changed.notify_all();
+
+        if sent {
+            failures = 0;
+            continue;
+        }
+
+        stream = None;
+        let delay = backoff.delay(failures);
+        failures = failures.saturating_add(1);
+        if shared.wait_for_shutdown(delay) {
+            return;
+        }
+    }
+}
+
+/// Sends a report over `stream`, connecting to the socket at `path` first if not yet connected.
+fn send(stream: &mut Option<UnixStream>, path: &Path, report: &PanicReport) -> io::Result<()> {
+    let connected = if let Some(connected) = stream.take() {
+        connected
+    } else {
+        let connected = UnixStream::connect(path)?;
+        connected.set_write_timeout(Some(WRITE_TIMEOUT))?;
+        connected
+    };
+
+    stream
+        .insert(connected)
+        .write_all(report.to_json_line().as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        os::unix::net::UnixListener,
+    };
+
+    use super::*;
+
+    use crate::Catcher;
+
+    /// Helper function to create a unique socket path for a test.
+    fn socket_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("chillpill-{name}-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    /// Helper function to read `n` reports from the first connection to `listener`, returning
+    /// their messages.
+    fn read_messages(listener: &UnixListener, n: usize) -> Vec<String> {
+        let (stream, _) = listener.accept().unwrap();
+        BufReader::new(stream)
+            .lines()
+            .take(n)
+            .map(|line| {
+                let line = line.unwrap();
+                assert!(line.starts_with("{\"schema_version\":1,"));
+                let (_, rest) = line.split_once("\"payload\":\"").unwrap();
+                let (message, _) = rest.split_once('"').unwrap();
+                message.to_string()
+            })
+            .collect()
+    }
+
+    /// This test ensures the drop policies drop the right item when the queue is full.
+    #[test]
+    fn drop_policies() {
+        let mut queue = VecDeque::from([1, 2]);
+        assert!(enqueue(&mut queue, 3, 2, DropPolicy::Newest));
+        assert_eq!(queue, [1, 2]);
+        assert!(enqueue(&mut queue, 3, 2, DropPolicy::Oldest));
+        assert_eq!(queue, [2, 3]);
+        assert!(!enqueue(&mut queue, 4, 3, DropPolicy::Newest));
+        assert_eq!(queue, [2, 3, 4]);
+    }
+
+    /// This test ensures caught panics are sent to the socket, one JSON line each.
+    #[test]
+    fn sends_reports() {
+        let path = socket_path("sends-reports");
+        let listener = UnixListener::bind(&path).unwrap();
+        let catcher = Catcher::new().reporter(UnixSocketReporter::start(&path).unwrap());
+
+        catcher.catch(|| panic!("first")).unwrap_err();
+        catcher.catch(|| panic!("second")).unwrap_err();
+
+        assert_eq!(read_messages(&listener, 2), ["first", "second"]);
+        drop(catcher);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// This test ensures reports are queued (and dropped once the queue is full) while the
+    /// collector is down, and sent once it comes up.
+    #[test]
+    fn reconnects_after_collector_starts() {
+        let path = socket_path("reconnects");
+        let reporter = Arc::new(
+            UnixSocketReporterConfig::new(&path)
+                .queue_capacity(2)
+                .drop_policy(DropPolicy::Oldest)
+                .backoff(Backoff::Fixed(Duration::from_millis(10)))
+                .start()
+                .unwrap(),
+        );
+        let catcher = Catcher::new().reporter(Arc::clone(&reporter));
+
+        for message in ["1", "2", "3"] {
+            catcher.catch(|| panic!("{message}")).unwrap_err();
+        }
+        assert_eq!(reporter.dropped(), 1);
+
+        let listener = UnixListener::bind(&path).unwrap();
+        assert_eq!(read_messages(&listener, 2), ["2", "3"]);
+        assert_eq!(reporter.queued(), 0);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// This test ensures flushing waits for queued reports to be sent, and gives up after the
+    /// timeout while the collector is down.
+    #[test]
+    fn flush_waits_for_reports() {
+        let path = socket_path("flush");
+        let reporter = Arc::new(
+            UnixSocketReporterConfig::new(&path)
+                .backoff(Backoff::Fixed(Duration::from_millis(10)))
+                .start()
+                .unwrap(),
+        );
+        let catcher = Catcher::new().reporter(Arc::clone(&reporter));
+
+        catcher.catch(|| panic!("queued")).unwrap_err();
+        assert!(!reporter.flush(Duration::from_millis(50)));
+        assert_eq!(reporter.queued(), 1);
+
+        let listener = UnixListener::bind(&path).unwrap();
+        assert!(reporter.flush(Duration::from_secs(10)));
+        assert_eq!(reporter.queued(), 0);
+        assert_eq!(read_messages(&listener, 1), ["queued"]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,81 @@
+#![allow(missing_docs, reason = "integration test")]
+
+use std::{fs, process::Command};
+
+/// When set, the test runs as the child process, and writes crash reports to
+/// the directory named by this environment variable.
+const CHILD_DIRECTORY_VAR: &str = "CHILLPILL_TEST_CRASH_REPORT_DIRECTORY";
+
+/// Runs the named test as a child process writing crash reports to a fresh
+/// directory, and returns the single crash report it wrote before aborting.
+fn crash_report_of_child(test_name: &str) -> String {
+    let directory =
+        std::env::temp_dir().join(format!("chillpill-{test_name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", test_name])
+        .env(CHILD_DIRECTORY_VAR, &directory)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let reports: Vec<_> = fs::read_dir(&directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert_eq!(reports.len(), 1);
+
+    let report = fs::read_to_string(&reports[0]).unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+    report
+}
+
+struct PanicOnDrop;
+
+impl Drop for PanicOnDrop {
+    fn drop(&mut self) {
+        panic!("panic during unwinding");
+    }
+}
+
+/// This test re-runs itself as a child process that installs a
+/// [`chillpill::CrashReporter`] and then panics while unwinding from another
+/// panic, aborting the process. It then ensures the child wrote a crash report
+/// describing both panics before aborting.
+#[test]
+fn crash_report_written_on_double_panic() {
+    if let Some(directory) = std::env::var_os(CHILD_DIRECTORY_VAR) {
+        chillpill::CrashReporter::new(directory).install();
+
+        let _guard = PanicOnDrop;
+        panic!("first panic");
+    }
+
+    let report = crash_report_of_child("crash_report_written_on_double_panic");
+    assert!(report.contains("reason: panic in a destructor during cleanup\n"));
+    assert!(report.contains("panic:\n  message: panic during unwinding\n"));
+    assert!(report.contains("previous in-flight panic:\n  message: first panic\n"));
+}
+
+extern "C" fn panic_in_extern_c() {
+    panic!("panic across extern \"C\"");
+}
+
+/// This test re-runs itself as a child process that installs a
+/// [`chillpill::CrashReporter`] and then panics in an `extern "C"` function,
+/// aborting the process. It then ensures the child wrote a crash report for the
+/// abort, so that a change to the standard library's wording fails this test
+/// rather than silently disabling abort detection.
+#[test]
+fn crash_report_written_on_panic_in_nounwind_function() {
+    if let Some(directory) = std::env::var_os(CHILD_DIRECTORY_VAR) {
+        chillpill::CrashReporter::new(directory).install();
+
+        panic_in_extern_c();
+    }
+
+    let report = crash_report_of_child("crash_report_written_on_panic_in_nounwind_function");
+    assert!(report.contains("reason: panic in a function that cannot unwind\n"));
+    assert!(report.contains("panic:\n  message: panic across extern \"C\"\n"));
+}
@@ -0,0 +1,42 @@
+#![allow(missing_docs, reason = "integration test")]
+
+use chillpill::UncaughtAction;
+
+/// This test ensures the flight recorder keeps the most recent panics, whether they were caught by
+/// chillpill, caught by `catch_unwind`, or not caught at all, up to its capacity.
+#[test]
+fn flight_recorder() {
+    chillpill::set_recent_panics_capacity(3);
+
+    // Keep the uncaught panics below quiet
+    chillpill::set_uncaught_handler(|_| UncaughtAction::Suppress);
+
+    chillpill::catch(|| panic!("first")).unwrap_err();
+    chillpill::catch(|| panic!("second")).unwrap_err();
+    std::panic::catch_unwind(|| panic!("third")).unwrap_err();
+    std::thread::Builder::new()
+        .name(String::from("recorded"))
+        .spawn(|| panic!("fourth"))
+        .unwrap()
+        .join()
+        .unwrap_err();
+
+    let recent_panics = chillpill::recent_panics();
+    let messages: Vec<_> = recent_panics
+        .iter()
+        .map(|snapshot| snapshot.message.as_deref().unwrap())
+        .collect();
+    assert_eq!(messages, ["second", "third", "fourth"]);
+    assert_eq!(recent_panics[2].thread.name.as_deref(), Some("recorded"));
+    assert_eq!(recent_panics[2].location.as_ref().unwrap().file, file!());
+    assert!(recent_panics[0].timestamp <= recent_panics[2].timestamp);
+
+    // Shrinking the capacity discards the oldest panics
+    chillpill::set_recent_panics_capacity(1);
+    assert_eq!(chillpill::recent_panics()[..], recent_panics[2..]);
+
+    // A capacity of zero disables the flight recorder
+    chillpill::set_recent_panics_capacity(0);
+    chillpill::catch(|| panic!("fifth")).unwrap_err();
+    assert!(chillpill::recent_panics().is_empty());
+}
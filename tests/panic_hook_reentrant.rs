@@ -0,0 +1,25 @@
+#![allow(missing_docs, reason = "integration test")]
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+static COUNTER: AtomicU8 = AtomicU8::new(0);
+
+/// This test ensures a hook registered through [`chillpill::panic::set_hook`] can replace itself
+/// while it runs, without deadlocking.
+#[test]
+fn panic_hook_replaces_itself() {
+    chillpill::panic::set_hook(Box::new(|_| {
+        COUNTER.fetch_add(1, Ordering::SeqCst);
+        chillpill::panic::set_hook(Box::new(|_| {
+            COUNTER.fetch_add(10, Ordering::SeqCst);
+        }));
+    }));
+
+    std::panic::catch_unwind(|| panic!("first")).unwrap_err();
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 1);
+
+    std::panic::catch_unwind(|| panic!("second")).unwrap_err();
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 11);
+
+    let _ = chillpill::panic::take_hook();
+}
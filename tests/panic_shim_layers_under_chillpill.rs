@@ -0,0 +1,52 @@
+#![allow(missing_docs, reason = "integration test")]
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+static COUNTER: AtomicU8 = AtomicU8::new(0);
+
+fn increment() {
+    COUNTER.fetch_add(1, Ordering::SeqCst);
+}
+
+/// This test registers a hook with a detectable side effect through
+/// [`chillpill::panic::set_hook`], then ensures that it is invoked for panics
+/// outside of [`chillpill::catch`], but not for panics within it. It also
+/// ensures [`chillpill::panic::update_hook`] and
+/// [`chillpill::panic::take_hook`] operate on that same hook.
+#[test]
+fn panic_shim_layers_under_chillpill() {
+    // Register a hook beneath the chillpill panic hook
+    chillpill::panic::set_hook(Box::new(|_| increment()));
+
+    // This panic should increment the counter
+    std::panic::catch_unwind(|| panic!()).unwrap_err();
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 1);
+
+    // This panic should *not* increment the counter
+    chillpill::catch(|| panic!()).unwrap_err();
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 1);
+
+    // Wrap the hook so that it increments the counter twice per panic
+    chillpill::panic::update_hook(|previous_hook, info| {
+        previous_hook(info);
+        increment();
+    });
+
+    // This panic should increment the counter twice
+    std::panic::catch_unwind(|| panic!()).unwrap_err();
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 3);
+
+    // This panic should still *not* increment the counter
+    chillpill::catch(|| panic!()).unwrap_err();
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 3);
+
+    // Taking the hook should hand it back, leaving the default hook in its place
+    let hook = chillpill::panic::take_hook();
+    std::panic::catch_unwind(|| panic!()).unwrap_err();
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 3);
+
+    // The taken hook should still work when re-registered
+    chillpill::panic::set_hook(hook);
+    std::panic::catch_unwind(|| panic!()).unwrap_err();
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 5);
+}
@@ -0,0 +1,62 @@
+#![allow(missing_docs, reason = "integration test")]
+
+use chillpill::{PanicCounts, PanicLocation, UncaughtAction};
+
+/// This test ensures panics are counted by whether they were caught, by location, and by thread,
+/// and that the counts can be reset.
+#[test]
+fn panic_stats() {
+    // Keep the uncaught panics below quiet
+    chillpill::set_uncaught_handler(|_| UncaughtAction::Suppress);
+
+    // A panic from before the reset should not be counted
+    chillpill::catch(|| panic!("before reset")).unwrap_err();
+    chillpill::reset_stats();
+    assert_eq!(chillpill::stats().total, PanicCounts::default());
+
+    let caught_location = PanicLocation {
+        file: String::from(file!()),
+        line: line!() + 4,
+        col: 9,
+    };
+    let panic_caught = || {
+        panic!("caught");
+    };
+    chillpill::catch(panic_caught).unwrap_err();
+    chillpill::catch(panic_caught).unwrap_err();
+
+    std::thread::Builder::new()
+        .name(String::from("stats-worker"))
+        .spawn(|| panic!("uncaught"))
+        .unwrap()
+        .join()
+        .unwrap_err();
+
+    let stats = chillpill::stats();
+    assert_eq!(
+        stats.total,
+        PanicCounts {
+            caught: 2,
+            uncaught: 1,
+        }
+    );
+    assert_eq!(
+        stats.by_location[&caught_location],
+        PanicCounts {
+            caught: 2,
+            uncaught: 0,
+        }
+    );
+    assert_eq!(
+        stats.by_thread[&Some(String::from("stats-worker"))],
+        PanicCounts {
+            caught: 0,
+            uncaught: 1,
+        }
+    );
+    assert_eq!(stats.by_location.len(), 2);
+    assert_eq!(stats.total.total(), 3);
+
+    chillpill::reset_stats();
+    assert_eq!(chillpill::stats(), chillpill::PanicStats::default());
+}
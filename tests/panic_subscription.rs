@@ -0,0 +1,57 @@
+#![allow(missing_docs, reason = "integration test")]
+
+use chillpill::{Catcher, UncaughtAction};
+
+/// This test ensures every subscriber receives an event for every panic, with the label of the
+/// catch that caught it, and that a full subscriber drops events instead of blocking (with a
+/// capacity of zero treated as one).
+#[test]
+fn panic_subscription() {
+    let events = chillpill::subscribe();
+    let other_events = chillpill::subscribe();
+    let slow_events = chillpill::subscribe_with_capacity(1);
+    let zero_capacity_events = chillpill::subscribe_with_capacity(0);
+    drop(chillpill::subscribe());
+
+    // Keep the uncaught panic below quiet
+    chillpill::set_uncaught_handler(|_| UncaughtAction::Suppress);
+
+    Catcher::new()
+        .label("labeled")
+        .catch(|| panic!("first"))
+        .unwrap_err();
+    chillpill::catch(|| panic!("second")).unwrap_err();
+    std::thread::Builder::new()
+        .spawn(|| panic!("third"))
+        .unwrap()
+        .join()
+        .unwrap_err();
+
+    for events in [&events, &other_events] {
+        let events: Vec<_> = events.try_iter().collect();
+        let summaries: Vec<_> = events
+            .iter()
+            .map(|event| {
+                (
+                    event.report.message.as_deref().unwrap(),
+                    event.caught,
+                    event.catch_label.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summaries,
+            [
+                ("first", true, Some("labeled")),
+                ("second", true, None),
+                ("third", false, None),
+            ]
+        );
+    }
+
+    for slow_events in [&slow_events, &zero_capacity_events] {
+        let slow_events: Vec<_> = slow_events.try_iter().collect();
+        assert_eq!(slow_events.len(), 1);
+        assert_eq!(slow_events[0].report.message.as_deref(), Some("first"));
+    }
+}
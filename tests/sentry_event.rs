@@ -0,0 +1,118 @@
+#![allow(missing_docs, reason = "integration test")]
+
+use std::{
+    backtrace::Backtrace,
+    path::Path,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use chillpill::{PanicLocation, PanicReport, ThreadInfo};
+use serde_json::Value;
+
+/// Helper function to create a report of a panic on this thread, with the given contents.
+fn make_report(
+    message: Option<&str>,
+    payload_type: Option<&'static str>,
+    location: Option<PanicLocation>,
+    thread_name: Option<&str>,
+) -> PanicReport {
+    PanicReport {
+        message: message.map(String::from),
+        payload_type,
+        context: Vec::new(),
+        tags: Vec::new(),
+        location,
+        backtrace: Arc::new(Backtrace::disabled()),
+        thread: ThreadInfo {
+            name: thread_name.map(String::from),
+            id: std::thread::current().id(),
+        },
+        timestamp: SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_250),
+    }
+}
+
+/// Helper function to generate the Sentry event of a report, replacing the values that vary
+/// between runs (the event ID and thread ID) with placeholders.
+fn normalized_event(report: &PanicReport) -> Value {
+    let mut event: Value = serde_json::from_str(&report.to_sentry_event()).unwrap();
+
+    let event_id = event["event_id"].as_str().unwrap();
+    assert_eq!(event_id.len(), 32);
+    assert!(event_id.bytes().all(|byte| byte.is_ascii_hexdigit()));
+    event["event_id"] = Value::from("<event_id>");
+
+    for pointer in ["/exception/values/0/thread_id", "/threads/values/0/id"] {
+        let thread_id = event.pointer_mut(pointer).unwrap();
+        assert!(thread_id.is_u64());
+        *thread_id = Value::from("<thread_id>");
+    }
+
+    event
+}
+
+/// Helper function to load a fixture from `tests/fixtures/sentry`.
+fn fixture(name: &str) -> Value {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/sentry")
+        .join(name);
+
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+/// This test ensures a report with a location, tags, and context maps to the expected event.
+#[test]
+fn structured_panic() {
+    let mut report = make_report(
+        Some("[E42] card declined user=42"),
+        Some("chillpill::StructuredPayload"),
+        Some(PanicLocation {
+            file: String::from("src/billing.rs"),
+            line: 12,
+            col: 9,
+        }),
+        Some("worker"),
+    );
+    report.context = vec![(String::from("user"), String::from("42"))];
+    report.tags = vec![
+        (String::from("service"), String::from("billing")),
+        (String::from("region"), String::from("eu")),
+    ];
+
+    assert_eq!(normalized_event(&report), fixture("structured_panic.json"));
+}
+
+/// This test ensures a report with no message, payload type, location, or thread name maps to the
+/// expected event.
+#[test]
+fn opaque_panic() {
+    let report = make_report(None, None, None, None);
+
+    assert_eq!(normalized_event(&report), fixture("opaque_panic.json"));
+}
+
+/// This test ensures a caught panic produces a well-formed event with the panic's location.
+#[test]
+fn caught_panic() {
+    let reporter = Arc::new(chillpill::MemoryReporter::new());
+    chillpill::Catcher::new()
+        .reporter(Arc::clone(&reporter))
+        .catch(|| panic!("oh no"))
+        .unwrap_err();
+
+    let report = &reporter.reports()[0];
+    let event = normalized_event(report);
+    assert_eq!(event["exception"]["values"][0]["value"], "oh no");
+    assert_eq!(
+        event["fingerprint"][0],
+        report.fingerprint().to_string().as_str()
+    );
+    if report.backtrace.status() != std::backtrace::BacktraceStatus::Captured {
+        let frames = event["exception"]["values"][0]["stacktrace"]["frames"]
+            .as_array()
+            .unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0]["filename"], file!());
+        assert_eq!(frames[0]["in_app"], true);
+    }
+}
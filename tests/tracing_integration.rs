@@ -0,0 +1,186 @@
+#![allow(missing_docs, reason = "integration test")]
+#![cfg(feature = "tracing")]
+
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+use chillpill::{MemoryReporter, PanicContextLayer, UncaughtAction};
+use tracing::{
+    Event, Subscriber,
+    field::{Field, Visit},
+};
+use tracing_subscriber::{
+    layer::{Context, Layer, SubscriberExt},
+    registry::LookupSpan,
+};
+
+/// An event recorded by [`EventCollector`].
+#[derive(Debug)]
+struct CollectedEvent {
+    /// The name of the span the event happened in.
+    span: Option<String>,
+
+    /// The fields of the event, formatted as strings.
+    fields: Vec<(String, String)>,
+}
+
+impl CollectedEvent {
+    fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// A layer that records every event.
+#[derive(Clone, Default)]
+struct EventCollector(Arc<Mutex<Vec<CollectedEvent>>>);
+
+impl EventCollector {
+    fn take(&self) -> Vec<CollectedEvent> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for EventCollector {
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        struct Fields(Vec<(String, String)>);
+
+        impl Visit for Fields {
+            fn record_str(&mut self, field: &Field, value: &str) {
+                self.0.push((field.name().to_string(), value.to_string()));
+            }
+
+            fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+                self.0
+                    .push((field.name().to_string(), format!("{value:?}")));
+            }
+        }
+
+        let mut fields = Fields(Vec::new());
+        event.record(&mut fields);
+        self.0.lock().unwrap().push(CollectedEvent {
+            span: ctx.event_span(event).map(|span| span.name().to_string()),
+            fields: fields.0,
+        });
+    }
+}
+
+/// This test ensures a caught panic is recorded as an event in the current span, and that the
+/// span's name and fields become the panic's context.
+#[test]
+fn caught_panic_recorded_in_span() {
+    let collector = EventCollector::default();
+    let subscriber = tracing_subscriber::registry()
+        .with(PanicContextLayer::new())
+        .with(collector.clone());
+
+    let panic_data = tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!(
+            "request",
+            user_id = 42,
+            path = "/pay",
+            status = tracing::field::Empty
+        );
+        let _span = span.enter();
+        span.record("status", "failed");
+
+        chillpill::catch_force_backtrace(|| panic!("oh no")).unwrap_err()
+    });
+
+    assert_eq!(
+        panic_data.context(),
+        [
+            (String::from("span"), String::from("request")),
+            (String::from("user_id"), String::from("42")),
+            (String::from("path"), String::from("/pay")),
+            (String::from("status"), String::from("failed")),
+        ]
+    );
+
+    let events = collector.take();
+    assert_eq!(events.len(), 1);
+    let event = &events[0];
+    assert_eq!(event.span.as_deref(), Some("request"));
+    assert_eq!(event.field("message"), Some("panic"));
+    assert_eq!(event.field("panic.message"), Some("oh no"));
+    assert_eq!(event.field("panic.file"), Some(file!()));
+    assert!(event.field("panic.line").is_some());
+    assert!(event.field("panic.column").is_some());
+    assert!(event.field("panic.backtrace").is_some());
+    assert_eq!(event.field("panic.caught"), Some("true"));
+}
+
+/// This test ensures the backtrace is only recorded if one was captured.
+#[test]
+fn backtrace_recorded_only_when_captured() {
+    let collector = EventCollector::default();
+    let subscriber = tracing_subscriber::registry().with(collector.clone());
+
+    tracing::subscriber::with_default(subscriber, || {
+        chillpill::catch_never_backtrace(|| panic!("no backtrace")).unwrap_err();
+    });
+
+    let events = collector.take();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].field("panic.message"), Some("no backtrace"));
+    assert_eq!(events[0].field("panic.backtrace"), None);
+}
+
+/// This test ensures that without a [`PanicContextLayer`], the context only includes the span's
+/// name, and that outside of any span, the context is empty.
+#[test]
+fn context_without_layer() {
+    let subscriber = tracing_subscriber::registry().with(EventCollector::default());
+
+    tracing::subscriber::with_default(subscriber, || {
+        let panic_data = chillpill::catch(|| panic!("outside")).unwrap_err();
+        assert!(panic_data.context().is_empty());
+
+        let _span = tracing::info_span!("request", user_id = 42).entered();
+        let panic_data = chillpill::catch(|| panic!("inside")).unwrap_err();
+        assert_eq!(
+            panic_data.context(),
+            [(String::from("span"), String::from("request"))]
+        );
+    });
+}
+
+/// This test ensures an uncaught panic is recorded as an event in the current span, and its report
+/// includes the span as context.
+#[test]
+fn uncaught_panic_recorded_in_span() {
+    let reporter = Arc::new(MemoryReporter::new());
+    chillpill::set_uncaught_reporter(Arc::clone(&reporter));
+    chillpill::set_uncaught_handler(|_| UncaughtAction::Suppress);
+
+    let collector = EventCollector::default();
+    let subscriber = tracing_subscriber::registry()
+        .with(PanicContextLayer::new())
+        .with(collector.clone());
+    std::thread::spawn(move || {
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = tracing::info_span!("worker", id = 7).entered();
+            panic!("uncaught");
+        });
+    })
+    .join()
+    .unwrap_err();
+
+    let events = collector.take();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].span.as_deref(), Some("worker"));
+    assert_eq!(events[0].field("panic.message"), Some("uncaught"));
+    assert_eq!(events[0].field("panic.caught"), Some("false"));
+
+    assert_eq!(
+        reporter.take_reports()[0].context,
+        [
+            (String::from("span"), String::from("worker")),
+            (String::from("id"), String::from("7")),
+        ]
+    );
+}
@@ -0,0 +1,51 @@
+#![allow(missing_docs, reason = "integration test")]
+
+use std::sync::{Arc, Mutex};
+
+use chillpill::{PanicReport, UncaughtAction};
+
+/// This test registers an uncaught panic handler that records every report it
+/// receives, then ensures it is invoked (with correct thread information) for
+/// panics outside of [`chillpill::catch`] on any thread, but not for panics
+/// within it.
+#[test]
+fn uncaught_handler() {
+    let reports: Arc<Mutex<Vec<PanicReport>>> = Arc::new(Mutex::new(Vec::new()));
+    let reports_copy = Arc::clone(&reports);
+    chillpill::set_uncaught_handler(move |report| {
+        reports_copy.lock().unwrap().push(report.clone());
+        UncaughtAction::Suppress
+    });
+
+    // This panic should be reported
+    std::panic::catch_unwind(|| panic!("uncaught on main")).unwrap_err();
+
+    // This panic should *not* be reported
+    chillpill::catch(|| panic!("caught")).unwrap_err();
+
+    // This panic should be reported, along with its thread name
+    let expected_line = line!() + 4;
+    std::thread::Builder::new()
+        .name(String::from("worker"))
+        .spawn(|| {
+            panic!("uncaught on worker");
+        })
+        .unwrap()
+        .join()
+        .unwrap_err();
+
+    // Once the handler is taken, panics should no longer be reported
+    assert!(chillpill::take_uncaught_handler().is_some());
+    std::panic::catch_unwind(|| panic!("no handler")).unwrap_err();
+
+    let reports = reports.lock().unwrap();
+    assert_eq!(reports.len(), 2);
+    assert_eq!(reports[0].message.as_deref(), Some("uncaught on main"));
+    assert_eq!(reports[1].message.as_deref(), Some("uncaught on worker"));
+    assert_eq!(reports[1].thread.name.as_deref(), Some("worker"));
+    assert_ne!(reports[0].thread.id, reports[1].thread.id);
+
+    let location = reports[1].location.as_ref().unwrap();
+    assert_eq!(location.file, file!());
+    assert_eq!(location.line, expected_line);
+}
@@ -0,0 +1,30 @@
+#![allow(missing_docs, reason = "integration test")]
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use chillpill::UncaughtAction;
+
+static COUNTER: AtomicU8 = AtomicU8::new(0);
+
+/// This test ensures an uncaught panic handler can unregister and replace itself while it runs,
+/// without deadlocking.
+#[test]
+fn uncaught_handler_replaces_itself() {
+    chillpill::set_uncaught_handler(|_| {
+        COUNTER.fetch_add(1, Ordering::SeqCst);
+        assert!(chillpill::take_uncaught_handler().is_some());
+        chillpill::set_uncaught_handler(|_| {
+            COUNTER.fetch_add(10, Ordering::SeqCst);
+            UncaughtAction::Suppress
+        });
+        UncaughtAction::Suppress
+    });
+
+    std::panic::catch_unwind(|| panic!("first")).unwrap_err();
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 1);
+
+    std::panic::catch_unwind(|| panic!("second")).unwrap_err();
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 11);
+
+    assert!(chillpill::take_uncaught_handler().is_some());
+}
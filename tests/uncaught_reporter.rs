@@ -0,0 +1,30 @@
+#![allow(missing_docs, reason = "integration test")]
+
+use std::sync::Arc;
+
+use chillpill::{MemoryReporter, UncaughtAction};
+
+/// This test ensures the uncaught reporter receives uncaught panics (and not caught ones), before
+/// the uncaught handler runs.
+#[test]
+fn reports_uncaught_panics() {
+    let reporter = Arc::new(MemoryReporter::new());
+    chillpill::set_uncaught_reporter(Arc::clone(&reporter));
+    chillpill::set_uncaught_handler(|_| UncaughtAction::Suppress);
+
+    chillpill::catch(|| panic!("caught")).unwrap_err();
+    std::thread::spawn(|| panic!("uncaught"))
+        .join()
+        .unwrap_err();
+
+    let reports = reporter.take_reports();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].message.as_deref(), Some("uncaught"));
+
+    assert!(chillpill::take_uncaught_reporter().is_some());
+    assert!(chillpill::take_uncaught_reporter().is_none());
+    std::thread::spawn(|| panic!("unreported"))
+        .join()
+        .unwrap_err();
+    assert!(reporter.reports().is_empty());
+}
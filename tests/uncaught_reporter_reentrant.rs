@@ -0,0 +1,31 @@
+#![allow(missing_docs, reason = "integration test")]
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use chillpill::{PanicReport, PanicReporter, UncaughtAction};
+
+static COUNTER: AtomicU8 = AtomicU8::new(0);
+
+/// A reporter that unregisters itself after its first report.
+struct OneShotReporter;
+
+impl PanicReporter for OneShotReporter {
+    fn report(&self, _report: &PanicReport) {
+        COUNTER.fetch_add(1, Ordering::SeqCst);
+        assert!(chillpill::take_uncaught_reporter().is_some());
+    }
+}
+
+/// This test ensures an uncaught panic reporter can unregister itself while it runs, without
+/// deadlocking.
+#[test]
+fn uncaught_reporter_replaces_itself() {
+    chillpill::set_uncaught_reporter(OneShotReporter);
+    chillpill::set_uncaught_handler(|_| UncaughtAction::Suppress);
+
+    std::panic::catch_unwind(|| panic!("first")).unwrap_err();
+    std::panic::catch_unwind(|| panic!("second")).unwrap_err();
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 1);
+
+    assert!(chillpill::take_uncaught_handler().is_some());
+}